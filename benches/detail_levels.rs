@@ -0,0 +1,188 @@
+/*
+Reports how many vertices each detail level draws, how long the map's mesh takes to rebuild at
+full detail versus the level picked at world zoom (the renderer rebuilds it and converts it into
+SFML's vertex layout whenever the level changes), and how long hovering takes to patch the
+highlighted shapes' colors in place. Run with `cargo bench --bench detail_levels`.
+*/
+use mapgame::{
+  config::MapConfig,
+  geo_drawable::{GeoDrawable, GeoPolygons, Vertex},
+  map_mesh::{Layer, MapMesh},
+  math::{Rect, Vector2f},
+  simplify::{DETAIL_TOLERANCES, simplify_shapes},
+  world_map::WorldMap,
+};
+use std::{
+  hint::black_box,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+const MAP_DIR: &str = "maps/earth";
+const BUILDS: u32 = 20;
+const HOVERS: u32 = 1000;
+// a 60fps frame
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+
+/* As laid out by SFML */
+#[allow(dead_code)]
+struct GpuVertex {
+  position: [f32; 2],
+  color: [u8; 4],
+  tex_coords: [f32; 2],
+}
+
+fn load_world_map() -> WorldMap {
+  let config = MapConfig::load("earth", Path::new(MAP_DIR)).expect("failed to load map config");
+  let mut world_map = WorldMap::new(&config).expect("failed to load map");
+  world_map.on_resize(&Rect::new(0.0, 0.0, 1920.0, 1080.0));
+  world_map
+}
+
+/* A fill per shape and a line strip per ring, as drawn before the map was batched */
+fn unbatched_draw_calls(geo_drawables: &[&GeoDrawable]) -> usize {
+  geo_drawables
+    .iter()
+    .map(|geo_drawable| 1 + geo_drawable.vector_polygons.iter().flatten().count())
+    .sum()
+}
+
+fn geo_drawables(world_map: &WorldMap) -> Vec<&GeoDrawable> {
+  world_map
+    .nations()
+    .values()
+    .flat_map(|nation| {
+      let provinces = nation
+        .provinces
+        .iter()
+        .flat_map(|provinces| provinces.values());
+      std::iter::once(nation.geo_drawable.as_ref())
+        .chain(provinces.map(|province| province.geo_drawable.as_ref()))
+    })
+    .collect()
+}
+
+fn to_gpu_vertex(vertex: &Vertex) -> GpuVertex {
+  let color = vertex.color;
+  GpuVertex {
+    position: [vertex.position.x, vertex.position.y],
+    color: [color.r, color.g, color.b, color.a],
+    tex_coords: [0.0, 0.0],
+  }
+}
+
+/* Builds the level's mesh and converts it for upload, as the renderer does whenever the detail
+level changes, returning how many vertices there were */
+fn build_mesh(world_map: &WorldMap, level: usize) -> usize {
+  let mesh = MapMesh::new(world_map, level);
+  Layer::ALL
+    .iter()
+    .map(|layer| {
+      let vertices: Vec<GpuVertex> = mesh
+        .layer(*layer)
+        .vertices
+        .iter()
+        .map(to_gpu_vertex)
+        .collect();
+      black_box(vertices).len()
+    })
+    .sum()
+}
+
+fn time_builds(name: &str, world_map: &WorldMap, level: usize) {
+  let start = Instant::now();
+  let mut vertices = 0;
+  for _ in 0..BUILDS {
+    vertices = build_mesh(world_map, level);
+  }
+  let per_build = start.elapsed() / BUILDS;
+  println!(
+    "{:<16} {:>9} vertices, {:>10.2?} per rebuild ({:.0}% of a 60fps frame)",
+    name,
+    vertices,
+    per_build,
+    per_build.as_secs_f64() / FRAME_BUDGET.as_secs_f64() * 100.0
+  );
+}
+
+/* Moves the cursor across the map, patching the highlighted shapes' vertices as the renderer does */
+fn time_hover(world_map: &mut WorldMap, level: usize) {
+  let mut mesh = MapMesh::new(world_map, level);
+  world_map.take_changed_shapes();
+  let start = Instant::now();
+  let mut patched = 0;
+  for i in 0..HOVERS {
+    let position = Vector2f::new(1920.0 * i as f32 / HOVERS as f32, 540.0);
+    world_map.set_highlighted_nation_at(position);
+    world_map.set_highlighted_province_at(position);
+    for key in world_map.take_changed_shapes() {
+      for (layer, range) in mesh.update_colors(world_map, &key) {
+        let vertices: Vec<GpuVertex> = mesh.layer(layer).vertices[range]
+          .iter()
+          .map(to_gpu_vertex)
+          .collect();
+        patched += black_box(vertices).len();
+      }
+    }
+  }
+  let per_hover = start.elapsed() / HOVERS;
+  println!(
+    "{:<16} {:>9} vertices patched, {:>10.2?} per cursor move",
+    "hover",
+    patched / HOVERS as usize,
+    per_hover
+  );
+}
+
+fn main() {
+  let load_start = Instant::now();
+  let mut world_map = load_world_map();
+  println!("loaded map in {:.2?}", load_start.elapsed());
+  let geo_drawables = geo_drawables(&world_map);
+
+  let shapes: Vec<&GeoPolygons> = geo_drawables
+    .iter()
+    .map(|geo_drawable| &geo_drawable.geo_polygons)
+    .collect();
+  let start = Instant::now();
+  black_box(simplify_shapes(&shapes, &DETAIL_TOLERANCES));
+  println!(
+    "simplified {} shapes at {} tolerances in {:.2?}",
+    shapes.len(),
+    DETAIL_TOLERANCES.len(),
+    start.elapsed()
+  );
+  println!();
+  println!("level  tolerance  outline vertices  fill vertices");
+  for level in 0..=DETAIL_TOLERANCES.len() {
+    let mesh = MapMesh::new(&world_map, level);
+    let count = |layers: [Layer; 2]| -> usize {
+      layers
+        .iter()
+        .map(|layer| mesh.layer(*layer).vertices.len())
+        .sum()
+    };
+    let outline = count([Layer::NationOutlines, Layer::ProvinceOutlines]);
+    let fill = count([Layer::NationFills, Layer::ProvinceFills]);
+    let tolerance = match level {
+      0 => "-".to_string(),
+      _ => DETAIL_TOLERANCES[level - 1].to_string(),
+    };
+    println!(
+      "{:<6} {:<10} {:>16} {:>14}",
+      level, tolerance, outline, fill
+    );
+  }
+
+  println!();
+  let world_level = world_map.detail_level();
+  println!(
+    "world zoom at 1920x1080 draws level {}, in {} draw calls rather than {}",
+    world_level,
+    Layer::ALL.len(),
+    unbatched_draw_calls(&geo_drawables)
+  );
+  time_builds("full detail", &world_map, 0);
+  time_builds(&format!("level {}", world_level), &world_map, world_level);
+  time_hover(&mut world_map, world_level);
+}
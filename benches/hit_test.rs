@@ -0,0 +1,97 @@
+/*
+Compares hit-testing through the world map's spatial index against a linear scan over every
+nation (and the hit nation's provinces). Run with `cargo bench --bench hit_test`.
+*/
+use mapgame::{
+  config::MapConfig,
+  math::{Rect, Vector2f},
+  world_map::WorldMap,
+};
+use std::{
+  hint::black_box,
+  path::Path,
+  time::{Duration, Instant},
+};
+
+const MAP_DIR: &str = "maps/earth";
+const QUERIES: usize = 10_000;
+
+fn load_world_map() -> WorldMap {
+  let config = MapConfig::load("earth", Path::new(MAP_DIR)).expect("failed to load map config");
+  let mut world_map = WorldMap::new(&config).expect("failed to load map");
+  world_map.on_resize(&Rect::new(0.0, 0.0, 1920.0, 1080.0));
+  world_map
+}
+
+/* Deterministic points spread over the map, from a simple linear congruential generator */
+fn sample_points(bounds: Rect, count: usize) -> Vec<Vector2f> {
+  let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+  let mut next = || {
+    state = state
+      .wrapping_mul(6_364_136_223_846_793_005)
+      .wrapping_add(1_442_695_040_888_963_407);
+    (state >> 40) as f32 / (1u64 << 24) as f32
+  };
+  (0..count)
+    .map(|_| Vector2f::new(bounds.width * next(), bounds.height * next()))
+    .collect()
+}
+
+fn linear_scan(world_map: &WorldMap, position: Vector2f) -> Option<(&String, Option<&String>)> {
+  let (nation_id, nation) = world_map
+    .nations()
+    .iter()
+    .find(|(_, nation)| nation.includes(position))?;
+  let province_id = nation.provinces.as_ref().and_then(|provinces| {
+    provinces
+      .iter()
+      .find(|(_, province)| province.geo_drawable.includes(position))
+      .map(|(id, _)| id)
+  });
+  Some((nation_id, province_id))
+}
+
+fn time(name: &str, points: &[Vector2f], mut query: impl FnMut(Vector2f) -> bool) {
+  let start = Instant::now();
+  let mut hits = 0;
+  for point in points {
+    if black_box(query(*point)) {
+      hits += 1;
+    }
+  }
+  let elapsed = start.elapsed();
+  let per_query = elapsed / points.len() as u32;
+  println!(
+    "{:<12} {:>10.2?} total, {:>10.2?} per query, {} hits",
+    name, elapsed, per_query, hits
+  );
+  if per_query > Duration::from_millis(1) {
+    println!("{:<12} slower than 1ms per query", name);
+  }
+}
+
+fn main() {
+  let load_start = Instant::now();
+  let world_map = load_world_map();
+  println!("loaded map in {:.2?}", load_start.elapsed());
+  let points = sample_points(Rect::new(0.0, 0.0, 1920.0, 1080.0), QUERIES);
+
+  let mismatches = points
+    .iter()
+    .filter(|point| {
+      let indexed = world_map.hit_test(**point).map(|hit| hit.nation_id);
+      indexed != linear_scan(&world_map, **point).map(|(nation_id, _)| nation_id)
+    })
+    .count();
+  println!(
+    "{} of {} queries disagree on the nation",
+    mismatches, QUERIES
+  );
+
+  time("linear scan", &points, |point| {
+    linear_scan(&world_map, point).is_some()
+  });
+  time("index", &points, |point| {
+    world_map.hit_test(point).is_some()
+  });
+}
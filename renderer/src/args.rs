@@ -0,0 +1,135 @@
+use mapgame::{
+  config::{ConfigOverrides, WindowMode, parse_window_size},
+  headless::HeadlessOptions,
+};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage: mapgame [compile|validate] [map] [options]
+
+options:
+  --map <name>            the map to play, compile or validate
+  --maps-dir <dir>        where maps are found
+  --config <file>         the config file, instead of the one in the user's config directory
+  --window-size <WxH>     the window's size in pixels, e.g. 1280x720
+  --fullscreen            play fullscreen
+  --borderless            play in a window without decorations covering the desktop
+  --windowed              play in a window, even if the config says otherwise
+  --print-config          print the resolved configuration and where it came from, then exit
+  --help                  print this message, then exit
+
+headless options, for running the game without a window:
+  --headless              run the simulation, then print a JSON summary of the final state
+  --ticks <n>             how many hourly ticks to run, 8760 (a year) by default
+  --script <file>         a JSON file with the players' scripted actions, and the number of ticks
+  --ai-players <n>        how many AI players to add after the script's players
+  --output <file>         where to write the summary, instead of printing it
+
+environment variables MAPGAME_MAP, MAPGAME_MAPS_DIR, MAPGAME_CONFIG, MAPGAME_WINDOW_SIZE and
+MAPGAME_FULLSCREEN are used for options not given";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+  Play,
+  Compile,
+  Validate,
+  Headless,
+}
+
+#[derive(Debug)]
+pub struct Args {
+  pub command: Command,
+  pub overrides: ConfigOverrides,
+  pub headless: HeadlessOptions,
+  pub print_config: bool,
+  pub help: bool,
+}
+
+impl Args {
+  /* Parses the arguments after the program name */
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+      command: Command::Play,
+      overrides: ConfigOverrides::default(),
+      headless: HeadlessOptions::default(),
+      print_config: false,
+      help: false,
+    };
+    let mut positional = Vec::new();
+    let mut headless = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+      // both `--flag value` and `--flag=value`
+      let (flag, inline_value) = match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => {
+          (flag.to_string(), Some(value.to_string()))
+        }
+        _ => (arg.clone(), None),
+      };
+      let mut value = || {
+        inline_value
+          .clone()
+          .or_else(|| args.next())
+          .ok_or_else(|| format!("{} needs a value", flag))
+      };
+      match flag.as_str() {
+        "--map" => parsed.overrides.map_name = Some(value()?),
+        "--maps-dir" => parsed.overrides.maps_dir = Some(value()?),
+        "--config" => parsed.overrides.config_path = Some(PathBuf::from(value()?)),
+        "--window-size" => {
+          let size = value()?;
+          let size = parse_window_size(&size)
+            .ok_or_else(|| format!("expected --window-size <width>x<height>, got {:?}", size))?;
+          parsed.overrides.window_size = Some(size);
+        }
+        "--fullscreen" => parsed.overrides.window_mode = Some(WindowMode::Fullscreen),
+        "--borderless" => parsed.overrides.window_mode = Some(WindowMode::Borderless),
+        "--windowed" => parsed.overrides.window_mode = Some(WindowMode::Windowed),
+        "--headless" => headless = true,
+        "--ticks" => parsed.headless.ticks = Some(parse_number(&flag, &value()?)?),
+        "--script" => parsed.headless.script = Some(PathBuf::from(value()?)),
+        "--ai-players" => parsed.headless.ai_players = parse_number(&flag, &value()?)?,
+        "--output" => parsed.headless.output = Some(PathBuf::from(value()?)),
+        "--print-config" => parsed.print_config = true,
+        "--help" | "-h" => parsed.help = true,
+        _ if flag.starts_with('-') => return Err(format!("unknown option {}", arg)),
+        _ => positional.push(arg),
+      }
+    }
+
+    let mut positional = positional.into_iter();
+    let mut map_name = None;
+    if let Some(first) = positional.next() {
+      match first.as_str() {
+        "compile" => parsed.command = Command::Compile,
+        "validate" => parsed.command = Command::Validate,
+        command => {
+          return Err(format!(
+            "unknown command {:?}, expected compile or validate",
+            command
+          ));
+        }
+      }
+      map_name = positional.next();
+    }
+    if let Some(extra) = positional.next() {
+      return Err(format!("unexpected argument {:?}", extra));
+    }
+    if headless {
+      if parsed.command != Command::Play {
+        return Err("--headless can't be combined with a command".to_string());
+      }
+      parsed.command = Command::Headless;
+    }
+    // `mapgame compile <map>` is shorthand for `mapgame compile --map <map>`
+    if parsed.overrides.map_name.is_none() {
+      parsed.overrides.map_name = map_name;
+    }
+    Ok(parsed)
+  }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+  value
+    .parse()
+    .map_err(|_| format!("expected {} <number>, got {:?}", flag, value))
+}
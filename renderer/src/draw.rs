@@ -0,0 +1,26 @@
+use mapgame::{
+  color::Color,
+  geo_drawable::Vertex,
+  math::{Rect, Vector2f},
+};
+use sfml::{graphics, system};
+
+pub fn to_sfml_vector(vector: Vector2f) -> system::Vector2f {
+  system::Vector2f::new(vector.x, vector.y)
+}
+
+pub fn from_sfml_vector(vector: system::Vector2f) -> Vector2f {
+  Vector2f::new(vector.x, vector.y)
+}
+
+pub fn to_sfml_color(color: Color) -> graphics::Color {
+  graphics::Color::rgba(color.r, color.g, color.b, color.a)
+}
+
+pub fn from_sfml_rect(rect: graphics::FloatRect) -> Rect {
+  Rect::new(rect.left, rect.top, rect.width, rect.height)
+}
+
+pub fn to_sfml_vertex(vertex: &Vertex) -> graphics::Vertex {
+  graphics::Vertex::with_pos_color(to_sfml_vector(vertex.position), to_sfml_color(vertex.color))
+}
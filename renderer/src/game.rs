@@ -0,0 +1,485 @@
+use crate::{
+  draw::{from_sfml_rect, from_sfml_vector, to_sfml_vector},
+  labels::Labels,
+  map_buffers::MapBuffers,
+};
+use mapgame::{
+  config::{Config, DisplayConfig, WindowMode, save_display_changes},
+  errors::MapLoadError,
+  map_cache::{CacheMiss, map_hash},
+  player::Player,
+  save::{QUICK_SAVE_NAME, SaveGame, autosave, is_autosave_due, save_path, saves_dir},
+  simulation::{GameState, SimulationClock},
+  world_map::WorldMap,
+};
+use sfml::{
+  cpp::FBox,
+  graphics::{Color, Rect, RenderTarget, RenderWindow, View},
+  system::{Clock, Vector2f, Vector2i},
+  window::{ContextSettings, Event, Key, Style, VideoMode, mouse::Button},
+};
+use std::{error::Error, path::PathBuf};
+
+const TITLE: &str = "mapgame";
+// each notch of the mouse wheel multiplies or divides the zoom by this much
+const ZOOM_STEP: f32 = 1.25;
+// cycled through in game, 0 being no limit
+const FRAME_LIMITS: [u32; 5] = [30, 60, 120, 144, 0];
+const ANTIALIASING_LEVELS: [u32; 4] = [0, 2, 4, 8];
+
+pub struct Game {
+  config: Config,
+  window: FBox<RenderWindow>,
+  world_map: Box<WorldMap>,
+  map_buffers: MapBuffers,
+  labels: Labels,
+  player: Box<Player>,
+  state: GameState,
+  // how many ticks of `state` to run each frame
+  simulation: SimulationClock,
+  // as last set, with the date and speed
+  title: String,
+  // none if there's no user config directory to save games in
+  saves_dir: Option<PathBuf>,
+  // hashed on the first save or load, as it reads the whole map
+  map_hash: Option<u64>,
+  clock: FBox<Clock>,
+  // last cursor position (in window pixels) while panning with the middle or right button
+  drag_position: Option<Vector2i>,
+}
+
+impl Game {
+  pub fn new(config: Config) -> Result<Game, Box<dyn Error>> {
+    let mut world_map = Box::new(WorldMap::new(&config.map)?);
+    // a missing cache is usual while editing a map, so isn't worth a mention
+    if let Some(miss) = world_map
+      .cache_miss()
+      .filter(|miss| !matches!(miss, CacheMiss::Missing))
+    {
+      println!("{}", miss);
+    }
+    println!(
+      "Loaded {} provinces for {} nations from map {}",
+      world_map.province_count(),
+      world_map.nations().len(),
+      config.map.name
+    );
+    let display = &config.view.display;
+    let (mode, style, settings) = window_params(display);
+    let mut window = RenderWindow::new(mode, TITLE, style, &settings)?;
+    configure_window(&mut window, display);
+    let player = Player::new();
+    let size = window.size();
+    world_map.on_resize(&from_sfml_rect(Rect::new(
+      0.0,
+      0.0,
+      size.x as f32,
+      size.y as f32,
+    )));
+    let mut game = Game {
+      config,
+      window,
+      world_map,
+      map_buffers: MapBuffers::default(),
+      labels: Labels::new()?,
+      player,
+      state: GameState::new(),
+      simulation: SimulationClock::new(),
+      title: TITLE.to_string(),
+      saves_dir: saves_dir(),
+      map_hash: None,
+      clock: Clock::start()?,
+      drag_position: None,
+    };
+    game.update_view();
+    Ok(game)
+  }
+
+  pub fn start(&mut self) {
+    while self.window.is_open() {
+      while let Some(event) = self.window.poll_event() {
+        self.on_event(event);
+      }
+      let delta = self.clock.restart().as_seconds();
+      let date = self.state.date;
+      self.simulation.advance(&mut self.state, delta as f64);
+      if is_autosave_due(&date, &self.state.date) {
+        self.autosave();
+      }
+      self.update_title();
+      self.update_camera(delta);
+      self.map_buffers.update(&mut self.world_map);
+      self.window.clear(Color::WHITE);
+      self.map_buffers.draw(&self.world_map, &mut *self.window);
+      self.labels.draw(&self.world_map, &mut *self.window);
+      self.window.display();
+    }
+  }
+
+  fn on_event(&mut self, event: Event) {
+    match event {
+      Event::Closed => self.on_close(),
+      Event::Resized { width, height } => {
+        self.on_resize(Rect::new(0.0, 0.0, width as f32, height as f32));
+      }
+      Event::MouseMoved { x, y } => {
+        self.on_mouse_move(Vector2i::new(x, y));
+      }
+      Event::MouseButtonPressed { button, x, y } => {
+        self.on_mouse_button_press(button, Vector2i::new(x, y));
+      }
+      Event::MouseButtonReleased { button, .. } => {
+        self.on_mouse_button_release(button);
+      }
+      Event::MouseWheelScrolled { delta, x, y, .. } => {
+        self.on_mouse_wheel_scroll(delta, Vector2i::new(x, y));
+      }
+      Event::KeyPressed { code, .. } => self.on_key_press(code),
+      _ => {}
+    }
+  }
+
+  /*
+  Space pauses, 1-5 set the speed and +/- change it. F5 quick saves and F6 quick loads. F7-F11 cycle
+  through the display settings
+  */
+  fn on_key_press(&mut self, key: Key) {
+    match key {
+      Key::Space => self.simulation.toggle_paused(),
+      Key::Num1 => self.simulation.set_speed(1),
+      Key::Num2 => self.simulation.set_speed(2),
+      Key::Num3 => self.simulation.set_speed(3),
+      Key::Num4 => self.simulation.set_speed(4),
+      Key::Num5 => self.simulation.set_speed(5),
+      Key::Add | Key::Equal => self.simulation.faster(),
+      Key::Subtract | Key::Hyphen => self.simulation.slower(),
+      Key::F5 => self.quick_save(),
+      Key::F6 => self.quick_load(),
+      _ => self.on_display_key_press(key),
+    }
+  }
+
+  fn on_display_key_press(&mut self, key: Key) {
+    let mut display = self.config.view.display;
+    match key {
+      Key::F7 => display.resolution = next_option(&resolutions(), display.resolution),
+      Key::F8 => display.antialiasing = next_option(&ANTIALIASING_LEVELS, display.antialiasing),
+      Key::F9 => display.frame_limit = next_option(&FRAME_LIMITS, display.frame_limit),
+      Key::F10 => display.vsync = !display.vsync,
+      Key::F11 => display.window_mode = display.window_mode.next(),
+      _ => return,
+    }
+    self.set_display(display);
+  }
+
+  /* Applies new display settings, recreating the window if needed, and saves the ones changed */
+  pub fn set_display(&mut self, display: DisplayConfig) {
+    let before = self.config.view.display;
+    if display == before {
+      return;
+    }
+    let recreate = display.resolution != before.resolution
+      || display.window_mode != before.window_mode
+      || display.antialiasing != before.antialiasing;
+    if recreate {
+      let (mode, style, settings) = window_params(&display);
+      self.window.recreate(mode, &self.title, style, &settings);
+    }
+    configure_window(&mut self.window, &display);
+    self.config.view.display = display;
+    if recreate {
+      let size = self.window.size();
+      self.on_resize(Rect::new(0.0, 0.0, size.x as f32, size.y as f32));
+    }
+    if let Some(path) = &self.config.save_path
+      && let Err(error) = save_display_changes(path, &before, &display)
+    {
+      println!("Failed to save display settings: {}", error);
+    }
+  }
+
+  fn quick_save(&mut self) {
+    let Some(dir) = &self.saves_dir else {
+      println!("Failed to save: there's no config directory to save in");
+      return;
+    };
+    let path = save_path(dir, QUICK_SAVE_NAME);
+    match self.save_game().and_then(|save| save.write(&path)) {
+      Ok(()) => println!("Saved to {}", path.display()),
+      Err(error) => println!("Failed to save: {}", error),
+    }
+  }
+
+  fn autosave(&mut self) {
+    let Some(dir) = self.saves_dir.clone() else {
+      return;
+    };
+    if let Err(error) = self.save_game().and_then(|save| autosave(&dir, &save)) {
+      println!("Failed to autosave: {}", error);
+    }
+  }
+
+  /* Loads the quick save, pausing the game */
+  fn quick_load(&mut self) {
+    let Some(dir) = &self.saves_dir else {
+      return;
+    };
+    let path = save_path(dir, QUICK_SAVE_NAME);
+    let result = SaveGame::read(&path).and_then(|save| {
+      let map_hash = self.map_hash()?;
+      save.check_map(&path, &self.config.map, map_hash)?;
+      Ok(save)
+    });
+    match result {
+      Ok(save) => {
+        self.load_game(save);
+        println!("Loaded {}", path.display());
+      }
+      Err(error) => println!("{}", error),
+    }
+  }
+
+  fn save_game(&mut self) -> Result<SaveGame, MapLoadError> {
+    let map_hash = self.map_hash()?;
+    Ok(SaveGame::new(
+      &self.config.map,
+      map_hash,
+      &self.state,
+      std::slice::from_ref(&*self.player),
+      self.world_map.ownership(),
+    ))
+  }
+
+  fn load_game(&mut self, save: SaveGame) {
+    let player = save.players.into_iter().next().unwrap_or(*Player::new());
+    self.world_map.set_selected_province(None);
+    self
+      .world_map
+      .set_selected_nation(self.player.nation_id.as_ref(), player.nation_id.as_ref());
+    *self.player = player;
+    self.world_map.set_ownership(&save.territory);
+    self.state = save.state;
+    self.simulation.set_paused(true);
+  }
+
+  fn map_hash(&mut self) -> Result<u64, MapLoadError> {
+    if let Some(map_hash) = self.map_hash {
+      return Ok(map_hash);
+    }
+    let map_hash = map_hash(&self.config.map)?;
+    self.map_hash = Some(map_hash);
+    Ok(map_hash)
+  }
+
+  /* Shows the date (to the day, so the title isn't reset every tick) and speed */
+  fn update_title(&mut self) {
+    let date = self.state.date;
+    let status = match self.simulation.is_paused() {
+      true => "paused".to_string(),
+      false => format!("speed {}", self.simulation.speed()),
+    };
+    let title = format!(
+      "{} - {} {} {} ({})",
+      TITLE,
+      date.day,
+      date.month_name(),
+      date.year,
+      status
+    );
+    if title != self.title {
+      self.window.set_title(&title);
+      self.title = title;
+    }
+  }
+
+  fn on_close(&mut self) {
+    self.window.close();
+  }
+
+  fn on_resize(&mut self, bounds: Rect<f32>) {
+    self.world_map.on_resize(&from_sfml_rect(bounds));
+    self.map_buffers.invalidate();
+    self.update_view();
+  }
+
+  fn on_mouse_move(&mut self, pixel: Vector2i) {
+    if !self.window.has_focus() {
+      return;
+    }
+    if let Some(drag_position) = self.drag_position {
+      let offset = drag_position - pixel;
+      self.pan_pixels(Vector2f::new(offset.x as f32, offset.y as f32));
+      self.drag_position = Some(pixel);
+    }
+    self.highlight_at(pixel);
+  }
+
+  /* Highlights the nation and province under the given window pixel */
+  fn highlight_at(&mut self, pixel: Vector2i) {
+    if !self.window.has_focus() {
+      return;
+    }
+    let position = self.to_map_position(pixel);
+    self.world_map.set_highlighted_nation_at(position);
+    self.world_map.set_highlighted_province_at(position);
+  }
+
+  fn on_mouse_button_press(&mut self, button: Button, pixel: Vector2i) {
+    match button {
+      Button::Left if self.player.nation_id.is_none() => {
+        let position = self.to_map_position(pixel);
+        let new_nation_id = self.world_map.set_selected_nation_at(position);
+        self.player.nation_id = new_nation_id;
+      }
+      Button::Left => {
+        let position = self.to_map_position(pixel);
+        self.world_map.set_selected_province_at(position);
+      }
+      Button::Middle | Button::Right => {
+        self.drag_position = Some(pixel);
+      }
+      _ => {}
+    }
+  }
+
+  fn on_mouse_button_release(&mut self, button: Button) {
+    if button == Button::Middle || button == Button::Right {
+      self.drag_position = None;
+    }
+  }
+
+  fn on_mouse_wheel_scroll(&mut self, delta: f32, pixel: Vector2i) {
+    let view = &self.config.view;
+    let zoom =
+      (self.world_map.target_zoom() * ZOOM_STEP.powf(delta)).clamp(view.min_zoom, view.max_zoom);
+    let anchor = self.to_map_position(pixel);
+    self.world_map.zoom_towards(zoom, anchor);
+  }
+
+  /* Advances zoom animation and scrolling, then re-highlights whatever is now under the cursor */
+  fn update_camera(&mut self, delta: f32) {
+    let zoomed = self.world_map.update_zoom(delta);
+    if zoomed {
+      self.update_view();
+    }
+    if self.scroll(delta) || zoomed {
+      self.highlight_at(self.window.mouse_position());
+    }
+  }
+
+  /* Applies keyboard (WASD/arrows) and screen-edge scrolling for a frame lasting `delta` seconds,
+  returning whether the view moved */
+  fn scroll(&mut self, delta: f32) -> bool {
+    if !self.window.has_focus() {
+      return false;
+    }
+    let mut direction = Vector2f::new(0.0, 0.0);
+    if Key::A.is_pressed() || Key::Left.is_pressed() {
+      direction.x -= 1.0;
+    }
+    if Key::D.is_pressed() || Key::Right.is_pressed() {
+      direction.x += 1.0;
+    }
+    if Key::W.is_pressed() || Key::Up.is_pressed() {
+      direction.y -= 1.0;
+    }
+    if Key::S.is_pressed() || Key::Down.is_pressed() {
+      direction.y += 1.0;
+    }
+    if self.drag_position.is_none() {
+      let mouse = self.window.mouse_position();
+      let size = self.window.size().as_other::<i32>();
+      let margin = self.config.view.edge_scroll_margin as i32;
+      let in_window = mouse.x >= 0 && mouse.x < size.x && mouse.y >= 0 && mouse.y < size.y;
+      if in_window {
+        if mouse.x < margin {
+          direction.x -= 1.0;
+        } else if mouse.x >= size.x - margin {
+          direction.x += 1.0;
+        }
+        if mouse.y < margin {
+          direction.y -= 1.0;
+        } else if mouse.y >= size.y - margin {
+          direction.y += 1.0;
+        }
+      }
+    }
+    if direction.x == 0.0 && direction.y == 0.0 {
+      return false;
+    }
+    self.pan_pixels(direction * (self.config.view.pan_speed * delta));
+    true
+  }
+
+  /* Pans the camera by an offset measured in window pixels */
+  fn pan_pixels(&mut self, offset: Vector2f) {
+    let scale = self.window.view().size().x / self.window.size().x as f32;
+    self.world_map.pan(from_sfml_vector(offset * scale));
+    self.update_view();
+  }
+
+  fn to_map_position(&self, pixel: Vector2i) -> mapgame::math::Vector2f {
+    from_sfml_vector(self.window.map_pixel_to_coords_current_view(pixel))
+  }
+
+  /* Derives the window's view from the world map's camera */
+  fn update_view(&mut self) {
+    let bounds = self.world_map.view_bounds();
+    let view = View::with_center_and_size(
+      to_sfml_vector(bounds.center()),
+      to_sfml_vector(bounds.size()),
+    );
+    self.window.set_view(&view);
+  }
+}
+
+/* The video mode, style and context to create a window with for the display settings */
+fn window_params(display: &DisplayConfig) -> (VideoMode, Style, ContextSettings) {
+  let desktop = VideoMode::desktop_mode();
+  let (width, height) = display.resolution;
+  let requested = VideoMode::new(width, height, desktop.bits_per_pixel);
+  let (mode, style) = match display.window_mode {
+    WindowMode::Windowed => (requested, Style::CLOSE),
+    // fullscreen only works with a mode the display supports
+    WindowMode::Fullscreen if requested.is_valid() => (requested, Style::FULLSCREEN),
+    WindowMode::Fullscreen => (desktop, Style::FULLSCREEN),
+    WindowMode::Borderless => (desktop, Style::NONE),
+  };
+  let settings = ContextSettings {
+    antialiasing_level: display.antialiasing,
+    ..Default::default()
+  };
+  (mode, style, settings)
+}
+
+/* Applies the display settings which don't need the window to be recreated */
+fn configure_window(window: &mut RenderWindow, display: &DisplayConfig) {
+  if display.window_mode == WindowMode::Borderless {
+    window.set_position(Vector2i::new(0, 0));
+  }
+  window.set_vertical_sync_enabled(display.vsync);
+  window.set_framerate_limit(if display.vsync {
+    0
+  } else {
+    display.frame_limit
+  });
+}
+
+/* The display's fullscreen resolutions, smallest first */
+fn resolutions() -> Vec<(u32, u32)> {
+  let mut resolutions: Vec<(u32, u32)> = VideoMode::fullscreen_modes()
+    .iter()
+    .map(|mode| (mode.width, mode.height))
+    .collect();
+  resolutions.sort();
+  resolutions.dedup();
+  resolutions
+}
+
+/* The option after `current`, wrapping around, or the first if `current` isn't one */
+fn next_option<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+  match options.iter().position(|option| *option == current) {
+    Some(index) => options[(index + 1) % options.len()],
+    None => options.first().copied().unwrap_or(current),
+  }
+}
@@ -0,0 +1,125 @@
+use crate::draw::{to_sfml_color, to_sfml_vector};
+use mapgame::{
+  color::Color,
+  geo_drawable::GeoDrawable,
+  label::{LabelCandidate, layout_labels},
+  math::{Rect, Vector2f},
+  world_map::WorldMap,
+};
+use sfml::{
+  SfResult,
+  cpp::FBox,
+  graphics::{Font, RenderTarget, Text, Transformable},
+};
+use std::collections::HashMap;
+
+// embedded, so the game starts from any directory
+const FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+// text is measured at this size, then scaled, as small sizes are rounded to whole pixels
+const MEASURE_SIZE: u32 = 64;
+// font sizes in pixels; labels which would be smaller are hidden
+const NATION_SIZES: (f32, f32) = (10.0, 28.0);
+const PROVINCE_SIZES: (f32, f32) = (9.0, 14.0);
+const NATION_TEXT_COLOR: Color = Color::rgb(40, 40, 40);
+const PROVINCE_TEXT_COLOR: Color = Color::rgb(90, 90, 90);
+const OUTLINE_COLOR: Color = Color::rgba(255, 255, 255, 200);
+
+/* Draws nation and province names in window pixels, so they stay sharp at any zoom */
+pub struct Labels {
+  font: FBox<Font>,
+  // by text, at a font size of 1
+  widths: HashMap<String, f32>,
+}
+
+impl Labels {
+  pub fn new() -> SfResult<Labels> {
+    Ok(Labels {
+      font: Font::from_memory_static(FONT)?,
+      widths: HashMap::new(),
+    })
+  }
+
+  /* Nations' names take priority over provinces', and larger shapes' over smaller ones' */
+  pub fn draw(&mut self, world_map: &WorldMap, target: &mut dyn RenderTarget) {
+    let view_bounds = world_map.view_bounds();
+    let size = target.size();
+    let scale = size.x as f32 / view_bounds.width;
+    let offset = Vector2f::new(-view_bounds.left, -view_bounds.top) * scale;
+
+    let mut nations: Vec<&GeoDrawable> = world_map
+      .nations()
+      .values()
+      .map(|nation| nation.geo_drawable.as_ref())
+      .collect();
+    // a nation's only province covers the whole nation, so it would just repeat the nation's name
+    let mut provinces: Vec<&GeoDrawable> = world_map
+      .nations()
+      .values()
+      .flat_map(|nation| {
+        nation
+          .provinces
+          .iter()
+          .filter(|provinces| provinces.len() > 1)
+      })
+      .flatten()
+      .map(|(_, province)| province.geo_drawable.as_ref())
+      .collect();
+    for drawables in [&mut nations, &mut provinces] {
+      drawables.sort_by(|a, b| b.vector_total_area.total_cmp(&a.vector_total_area));
+    }
+
+    let mut shapes = Vec::new();
+    let mut candidates = Vec::new();
+    for (drawables, (min_size, max_size), color) in [
+      (&nations, NATION_SIZES, NATION_TEXT_COLOR),
+      (&provinces, PROVINCE_SIZES, PROVINCE_TEXT_COLOR),
+    ] {
+      for drawable in drawables {
+        let Some(anchor) = drawable.label else {
+          continue;
+        };
+        candidates.push(LabelCandidate {
+          anchor: anchor.transform(scale, offset),
+          text_width: self.width(&drawable.name),
+          min_size,
+          max_size,
+        });
+        shapes.push((&drawable.name, color));
+      }
+    }
+    let viewport = Rect::new(0.0, 0.0, size.x as f32, size.y as f32);
+    let placed = layout_labels(&candidates, viewport);
+
+    let map_view = target.view().to_owned();
+    let window_view = target.default_view().to_owned();
+    target.set_view(&window_view);
+    for label in placed {
+      let (name, color) = shapes[label.index];
+      let mut text = Text::new(name.as_str(), &self.font, label.size.round() as u32);
+      let bounds = text.local_bounds();
+      text.set_origin((
+        bounds.left + bounds.width / 2.0,
+        bounds.top + bounds.height / 2.0,
+      ));
+      text.set_position(to_sfml_vector(label.position));
+      text.set_rotation(label.angle);
+      text.set_fill_color(to_sfml_color(color));
+      text.set_outline_color(to_sfml_color(OUTLINE_COLOR));
+      text.set_outline_thickness(1.0);
+      target.draw(&text);
+    }
+    target.set_view(&map_view);
+  }
+
+  fn width(&mut self, text: &str) -> f32 {
+    if let Some(width) = self.widths.get(text) {
+      return *width;
+    }
+    let width = Text::new(text, &self.font, MEASURE_SIZE)
+      .local_bounds()
+      .width
+      / MEASURE_SIZE as f32;
+    self.widths.insert(text.to_string(), width);
+    width
+  }
+}
@@ -0,0 +1,81 @@
+#[cfg(target_os = "windows")]
+#[link(name = "Advapi32")]
+unsafe extern "system" {}
+
+pub mod args;
+pub mod draw;
+pub mod game;
+pub mod labels;
+pub mod map_buffers;
+
+use args::{Args, Command, USAGE};
+use game::Game;
+use mapgame::{
+  config::{Config, describe_config, load_config},
+  headless::{HeadlessOptions, run_headless},
+  map_cache,
+  validate::{Severity, validate_map},
+};
+use std::{env, error::Error, fs};
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let args = Args::parse(env::args().skip(1)).map_err(|error| format!("{}\n\n{}", error, USAGE))?;
+  if args.help {
+    println!("{}", USAGE);
+    return Ok(());
+  }
+  if args.print_config {
+    println!("{}", describe_config(&args.overrides)?);
+    return Ok(());
+  }
+  let config = load_config(&args.overrides)?;
+  match args.command {
+    Command::Play => {
+      let mut game = Game::new(config)?;
+      game.start();
+      Ok(())
+    }
+    Command::Compile => compile(config),
+    Command::Validate => validate(config),
+    Command::Headless => headless(config, &args.headless),
+  }
+}
+
+/* `mapgame --headless`: runs the simulation without a window, then writes a JSON summary */
+fn headless(config: Config, options: &HeadlessOptions) -> Result<(), Box<dyn Error>> {
+  let summary = run_headless(&config, options)?;
+  let json = serde_json::to_string_pretty(&summary)?;
+  match &options.output {
+    Some(path) => fs::write(path, json + "\n")?,
+    None => println!("{}", json),
+  }
+  Ok(())
+}
+
+/* `mapgame compile [map]`: compiles the map (or the configured one) into its binary cache */
+fn compile(config: Config) -> Result<(), Box<dyn Error>> {
+  let path = map_cache::compile(&config.map)?;
+  println!("Compiled map {} to {:?}", config.map.name, path);
+  Ok(())
+}
+
+/* `mapgame validate [map]`: reports every problem with the map (or the configured one) */
+fn validate(config: Config) -> Result<(), Box<dyn Error>> {
+  let problems = validate_map(&config.map);
+  for problem in &problems {
+    println!("{}", problem);
+  }
+  let errors = problems
+    .iter()
+    .filter(|problem| problem.severity == Severity::Error)
+    .count();
+  let warnings = problems.len() - errors;
+  println!(
+    "Map {}: {} errors, {} warnings",
+    config.map.name, errors, warnings
+  );
+  if errors > 0 {
+    return Err(format!("map {} is invalid", config.map.name).into());
+  }
+  Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::draw::to_sfml_vertex;
+use mapgame::{
+  map_mesh::{Layer, MapMesh, ShapeKey},
+  world_map::WorldMap,
+};
+use sfml::{
+  SfResult,
+  cpp::FBox,
+  graphics::{PrimitiveType, RenderStates, RenderTarget, Vertex, VertexBuffer, VertexBufferUsage},
+};
+use std::ops::Range;
+
+/*
+The world map's mesh, uploaded into one vertex buffer per layer so the whole map draws in a
+handful of calls. Highlighting and selection patch the changed shapes' vertices in place; the
+mesh is only rebuilt on resize, when the detail level changes or when territory changes hands.
+Where vertex buffers aren't supported, each layer is drawn straight from the mesh instead, still
+in one call.
+*/
+#[derive(Default)]
+pub struct MapBuffers {
+  mesh: Option<MapMesh>,
+  // indexed as in `Layer::ALL`, empty when vertex buffers aren't supported
+  buffers: Vec<FBox<VertexBuffer>>,
+}
+
+impl MapBuffers {
+  /* Rebuilds the mesh on the next update, as the map's geometry has moved */
+  pub fn invalidate(&mut self) {
+    self.mesh = None;
+  }
+
+  /* Brings the buffers up to date with the world map's detail level, borders and colors. If uploading
+  fails, the layers are drawn from the mesh until it's next rebuilt */
+  pub fn update(&mut self, world_map: &mut WorldMap) {
+    let level = world_map.detail_level();
+    let changed = world_map.take_changed_shapes();
+    if world_map.take_changed_geometry() {
+      self.invalidate();
+    }
+    let Some(mesh) = self.mesh.as_mut().filter(|mesh| mesh.level == level) else {
+      // a new mesh has every shape's current colors
+      let mesh = MapMesh::new(world_map, level);
+      self.buffers = upload(&mesh).unwrap_or_default();
+      self.mesh = Some(mesh);
+      return;
+    };
+    for key in changed {
+      for (layer, range) in mesh.update_colors(world_map, &key) {
+        let Some(buffer) = self.buffers.get_mut(layer as usize) else {
+          continue;
+        };
+        let vertices = to_sfml_vertices(mesh, layer, range.clone());
+        if buffer.update(&vertices, range.start as u32).is_err() {
+          self.buffers.clear();
+        }
+      }
+    }
+  }
+
+  /* Highlighted and selected outlines go last, so they aren't covered by neighbouring borders */
+  pub fn draw(&self, world_map: &WorldMap, target: &mut dyn RenderTarget) {
+    let Some(mesh) = self.mesh.as_ref() else {
+      return;
+    };
+    let states = RenderStates::default();
+    for layer in Layer::ALL {
+      match self.buffers.get(layer as usize) {
+        Some(buffer) if buffer.vertex_count() > 0 => target.draw_vertex_buffer(buffer, &states),
+        Some(_) => {}
+        None => draw_range(mesh, layer, 0..mesh.layer(layer).vertices.len(), target),
+      }
+    }
+
+    let mut highlights = Vec::new();
+    if let Some(nation) = world_map.get_highlighted_nation() {
+      highlights.push((Layer::NationOutlines, ShapeKey::Nation(nation.id().clone())));
+    }
+    for key in [
+      world_map.selected_province(),
+      world_map.highlighted_province(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+      let key = ShapeKey::Province(key.clone());
+      highlights.push((Layer::ProvinceOutlines, key));
+    }
+    for (layer, key) in highlights {
+      if let Some(range) = mesh.layer(layer).range(&key) {
+        draw_range(mesh, layer, range, target);
+      }
+    }
+  }
+}
+
+/* One buffer per layer, or none if vertex buffers aren't supported */
+fn upload(mesh: &MapMesh) -> SfResult<Vec<FBox<VertexBuffer>>> {
+  let mut buffers = Vec::new();
+  if !VertexBuffer::available() {
+    return Ok(buffers);
+  }
+  for layer in Layer::ALL {
+    let vertices = to_sfml_vertices(mesh, layer, 0..mesh.layer(layer).vertices.len());
+    let mut buffer = VertexBuffer::new(
+      primitive_type(layer),
+      vertices.len(),
+      VertexBufferUsage::DYNAMIC,
+    )?;
+    if !vertices.is_empty() {
+      buffer.update(&vertices, 0)?;
+    }
+    buffers.push(buffer);
+  }
+  Ok(buffers)
+}
+
+fn primitive_type(layer: Layer) -> PrimitiveType {
+  match layer.is_fill() {
+    true => PrimitiveType::TRIANGLES,
+    false => PrimitiveType::LINES,
+  }
+}
+
+fn to_sfml_vertices(mesh: &MapMesh, layer: Layer, range: Range<usize>) -> Vec<Vertex> {
+  mesh.layer(layer).vertices[range]
+    .iter()
+    .map(to_sfml_vertex)
+    .collect()
+}
+
+fn draw_range(mesh: &MapMesh, layer: Layer, range: Range<usize>, target: &mut dyn RenderTarget) {
+  let vertices = to_sfml_vertices(mesh, layer, range);
+  if !vertices.is_empty() {
+    target.draw_primitives(&vertices, primitive_type(layer), &RenderStates::default());
+  }
+}
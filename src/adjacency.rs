@@ -0,0 +1,417 @@
+use crate::{
+  config::MapConfig,
+  errors::MapLoadError,
+  files::read_json,
+  geo::{Point, segment_distance},
+  geo_drawable::GeoPolygons,
+  nation::Nations,
+  projection::GeoPoint,
+  province::ProvinceKey,
+};
+use serde::Deserialize;
+use std::{
+  collections::{BTreeMap, HashMap},
+  hash::Hash,
+};
+
+// in degrees; borders from different sources rarely line up exactly
+pub const BORDER_TOLERANCE: f64 = 0.01;
+// in degrees, for bucketing border segments
+const CELL_SIZE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderKind {
+  Land,
+  // added by hand, between shapes which don't touch
+  Sea,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Border {
+  pub kind: BorderKind,
+  // in kilometres, 0 for sea links
+  pub length: f64,
+}
+
+/* An undirected graph of which shapes border each other */
+#[derive(Debug)]
+pub struct AdjacencyGraph<K> {
+  borders: HashMap<K, HashMap<K, Border>>,
+}
+
+impl<K: Clone + Eq + Hash> AdjacencyGraph<K> {
+  pub fn new() -> AdjacencyGraph<K> {
+    AdjacencyGraph {
+      borders: HashMap::new(),
+    }
+  }
+
+  /* Adds or replaces the border between `a` and `b` */
+  pub fn insert(&mut self, a: &K, b: &K, border: Border) {
+    if a == b {
+      return;
+    }
+    self
+      .borders
+      .entry(a.clone())
+      .or_default()
+      .insert(b.clone(), border);
+    self
+      .borders
+      .entry(b.clone())
+      .or_default()
+      .insert(a.clone(), border);
+  }
+
+  pub fn remove(&mut self, a: &K, b: &K) {
+    if let Some(borders) = self.borders.get_mut(a) {
+      borders.remove(b);
+    }
+    if let Some(borders) = self.borders.get_mut(b) {
+      borders.remove(a);
+    }
+  }
+
+  pub fn neighbours(&self, key: &K) -> impl Iterator<Item = &K> {
+    self.borders(key).map(|(neighbour, _)| neighbour)
+  }
+
+  pub fn borders(&self, key: &K) -> impl Iterator<Item = (&K, &Border)> {
+    self.borders.get(key).into_iter().flatten()
+  }
+
+  pub fn border(&self, a: &K, b: &K) -> Option<&Border> {
+    self.borders.get(a)?.get(b)
+  }
+
+  pub fn are_adjacent(&self, a: &K, b: &K) -> bool {
+    self.border(a, b).is_some()
+  }
+
+  /* In kilometres, or None if `a` and `b` aren't adjacent */
+  pub fn shared_border_length(&self, a: &K, b: &K) -> Option<f64> {
+    self.border(a, b).map(|border| border.length)
+  }
+
+  /* Every border, once from each side */
+  pub fn iter(&self) -> impl Iterator<Item = (&K, &K, &Border)> {
+    self
+      .borders
+      .iter()
+      .flat_map(|(a, borders)| borders.iter().map(move |(b, border)| (a, b, border)))
+  }
+
+  /* The number of pairs of adjacent shapes */
+  pub fn len(&self) -> usize {
+    self
+      .borders
+      .values()
+      .map(|borders| borders.len())
+      .sum::<usize>()
+      / 2
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+impl<K: Clone + Eq + Hash> Default for AdjacencyGraph<K> {
+  fn default() -> Self {
+    AdjacencyGraph::new()
+  }
+}
+
+/* Which nations border each other, and which provinces do (across nations too) */
+#[derive(Debug, Default)]
+pub struct Adjacency {
+  pub nations: AdjacencyGraph<String>,
+  pub provinces: AdjacencyGraph<ProvinceKey>,
+}
+
+/* One end of a sea link; without a province, the link is between whole nations */
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeaLinkEnd {
+  pub nation: String,
+  pub province: Option<String>,
+}
+
+/* A manual override in the map's sea links file, adding (or with `remove`, cutting) a link */
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeaLink {
+  pub from: SeaLinkEnd,
+  pub to: SeaLinkEnd,
+  #[serde(default)]
+  pub remove: bool,
+}
+
+impl Adjacency {
+  /* Computes land borders from the nations' and provinces' shapes, then applies the sea links.
+  Links referring to nations or provinces which aren't loaded are ignored */
+  pub fn build(nations: &Nations, sea_links: &[SeaLink]) -> Adjacency {
+    let mut adjacency = Adjacency::default();
+
+    let mut nation_ids: Vec<&String> = nations.keys().collect();
+    nation_ids.sort();
+    let shapes: Vec<&GeoPolygons> = nation_ids
+      .iter()
+      .map(|id| &nations[*id].geo_drawable.geo_polygons)
+      .collect();
+    for ((a, b), length) in find_shared_borders(&shapes, BORDER_TOLERANCE) {
+      let border = Border {
+        kind: BorderKind::Land,
+        length,
+      };
+      adjacency
+        .nations
+        .insert(nation_ids[a], nation_ids[b], border);
+    }
+
+    let mut province_keys = Vec::new();
+    let mut shapes = Vec::new();
+    for nation_id in &nation_ids {
+      let mut provinces: Vec<_> = nations[*nation_id].provinces.iter().flatten().collect();
+      provinces.sort_by_key(|(province_id, _)| *province_id);
+      for (province_id, province) in provinces {
+        province_keys.push(ProvinceKey::new(nation_id, province_id));
+        shapes.push(&province.geo_drawable.geo_polygons);
+      }
+    }
+    for ((a, b), length) in find_shared_borders(&shapes, BORDER_TOLERANCE) {
+      let border = Border {
+        kind: BorderKind::Land,
+        length,
+      };
+      adjacency
+        .provinces
+        .insert(&province_keys[a], &province_keys[b], border);
+    }
+
+    for link in sea_links {
+      adjacency.apply_sea_link(nations, link);
+    }
+    adjacency
+  }
+
+  /* Recomputes the land borders of nations whose shapes have changed, keeping their sea links.
+  A nation left with no territory borders nothing */
+  pub fn update_nations(&mut self, nations: &Nations, nation_ids: &[&String]) {
+    let mut changed: Vec<&String> = nation_ids
+      .iter()
+      .copied()
+      .filter(|id| nations.contains_key(*id))
+      .collect();
+    changed.sort();
+    changed.dedup();
+    for nation_id in &changed {
+      let stale: Vec<String> = self
+        .nations
+        .borders(nation_id)
+        .filter(|(_, border)| {
+          border.kind == BorderKind::Land
+            || nations[*nation_id].geo_drawable.geo_polygons.is_empty()
+        })
+        .map(|(neighbour, _)| neighbour.clone())
+        .collect();
+      for neighbour in &stale {
+        self.nations.remove(nation_id, neighbour);
+      }
+    }
+
+    // only nations near a changed one can border it
+    let changed_bounds: Vec<_> = changed
+      .iter()
+      .filter_map(|id| geo_bounds(&nations[*id].geo_drawable.geo_polygons))
+      .collect();
+    let mut others: Vec<&String> = nations
+      .keys()
+      .filter(|id| !changed.contains(id))
+      .filter(|id| {
+        geo_bounds(&nations[*id].geo_drawable.geo_polygons).is_some_and(|bounds| {
+          changed_bounds
+            .iter()
+            .any(|changed| bounds_overlap(changed, &bounds, BORDER_TOLERANCE))
+        })
+      })
+      .collect();
+    others.sort();
+    let nation_ids: Vec<&String> = changed.iter().chain(&others).copied().collect();
+    let shapes: Vec<&GeoPolygons> = nation_ids
+      .iter()
+      .map(|id| &nations[*id].geo_drawable.geo_polygons)
+      .collect();
+    // pairs are lowest index first, and the changed nations come first
+    for ((a, b), length) in find_shared_borders(&shapes, BORDER_TOLERANCE) {
+      if a >= changed.len() {
+        continue;
+      }
+      let border = Border {
+        kind: BorderKind::Land,
+        length,
+      };
+      self.nations.insert(nation_ids[a], nation_ids[b], border);
+    }
+  }
+
+  pub fn load_sea_links(config: &MapConfig) -> Result<Vec<SeaLink>, MapLoadError> {
+    if !config.sea_links_path.exists() {
+      return Ok(Vec::new());
+    }
+    read_json(&config.sea_links_path)
+  }
+
+  fn apply_sea_link(&mut self, nations: &Nations, link: &SeaLink) {
+    let (from, to) = (&link.from, &link.to);
+    if !nations.contains_key(&from.nation) || !nations.contains_key(&to.nation) {
+      return;
+    }
+    let sea_border = Border {
+      kind: BorderKind::Sea,
+      length: 0.0,
+    };
+    if let (Some(from_province), Some(to_province)) = (&from.province, &to.province) {
+      let has_province = |end: &SeaLinkEnd, province_id: &String| {
+        nations[&end.nation]
+          .provinces
+          .as_ref()
+          .is_some_and(|provinces| provinces.contains_key(province_id))
+      };
+      if !has_province(from, from_province) || !has_province(to, to_province) {
+        return;
+      }
+      let from_key = ProvinceKey::new(&from.nation, from_province);
+      let to_key = ProvinceKey::new(&to.nation, to_province);
+      if link.remove {
+        self.provinces.remove(&from_key, &to_key);
+        return;
+      }
+      self.provinces.insert(&from_key, &to_key, sea_border);
+    } else if link.remove {
+      self.nations.remove(&from.nation, &to.nation);
+      return;
+    }
+    // a sea link between provinces also links their nations, unless they already share land
+    if !self.nations.are_adjacent(&from.nation, &to.nation) {
+      self.nations.insert(&from.nation, &to.nation, sea_border);
+    }
+  }
+}
+
+type Segment = (Point, Point);
+
+/*
+The length in kilometres of border shared by each pair of shapes (by index, lowest first), where
+a border segment of one shape counts as shared if its ends and middle are all within `tolerance`
+degrees of the other shape's border. Measured from both sides, keeping the longer.
+*/
+pub fn find_shared_borders(
+  shapes: &[&GeoPolygons],
+  tolerance: f64,
+) -> BTreeMap<(usize, usize), f64> {
+  let grid = SegmentGrid::new(shapes, tolerance);
+  // per pair, the length measured along the lower and the higher index's border
+  let mut lengths: HashMap<(usize, usize), (f64, f64)> = HashMap::new();
+  for (index, polygons) in shapes.iter().enumerate() {
+    for (a, b) in segments(polygons) {
+      let middle = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+      let (near_a, near_b) = (grid.shapes_near(a, index), grid.shapes_near(b, index));
+      let near_all = grid
+        .shapes_near(middle, index)
+        .into_iter()
+        .filter(|other| near_a.contains(other) && near_b.contains(other));
+      let length = GeoPoint::new(a[0], a[1]).distance(GeoPoint::new(b[0], b[1]));
+      for other in near_all {
+        let pair = (index.min(other), index.max(other));
+        let entry = lengths.entry(pair).or_default();
+        if index < other {
+          entry.0 += length;
+        } else {
+          entry.1 += length;
+        }
+      }
+    }
+  }
+  lengths
+    .into_iter()
+    .map(|(pair, (lower, higher))| (pair, lower.max(higher)))
+    .filter(|(_, length)| *length > 0.0)
+    .collect()
+}
+
+/* The lowest and highest corners of a shape's bounding box, or None if it's empty */
+fn geo_bounds(polygons: &GeoPolygons) -> Option<(Point, Point)> {
+  let mut points = polygons.iter().flatten().flatten();
+  let first = points.next()?;
+  let start = ([first[0], first[1]], [first[0], first[1]]);
+  Some(points.fold(start, |(min, max), point| {
+    (
+      [min[0].min(point[0]), min[1].min(point[1])],
+      [max[0].max(point[0]), max[1].max(point[1])],
+    )
+  }))
+}
+
+fn bounds_overlap(a: &(Point, Point), b: &(Point, Point), tolerance: f64) -> bool {
+  a.0[0] - tolerance <= b.1[0]
+    && b.0[0] - tolerance <= a.1[0]
+    && a.0[1] - tolerance <= b.1[1]
+    && b.0[1] - tolerance <= a.1[1]
+}
+
+fn segments(polygons: &GeoPolygons) -> impl Iterator<Item = Segment> + '_ {
+  polygons
+    .iter()
+    .flatten()
+    .flat_map(|ring| ring.windows(2))
+    .map(|pair| ([pair[0][0], pair[0][1]], [pair[1][0], pair[1][1]]))
+    .filter(|(a, b)| a != b)
+}
+
+/* Buckets every shape's border segments by the grid cells they pass near */
+struct SegmentGrid {
+  tolerance: f64,
+  cells: HashMap<(i64, i64), Vec<(usize, Segment)>>,
+}
+
+impl SegmentGrid {
+  fn new(shapes: &[&GeoPolygons], tolerance: f64) -> SegmentGrid {
+    let mut cells: HashMap<(i64, i64), Vec<(usize, Segment)>> = HashMap::new();
+    for (index, polygons) in shapes.iter().enumerate() {
+      for (a, b) in segments(polygons) {
+        let (min_x, min_y) = to_cell([a[0].min(b[0]) - tolerance, a[1].min(b[1]) - tolerance]);
+        let (max_x, max_y) = to_cell([a[0].max(b[0]) + tolerance, a[1].max(b[1]) + tolerance]);
+        for x in min_x..=max_x {
+          for y in min_y..=max_y {
+            cells.entry((x, y)).or_default().push((index, (a, b)));
+          }
+        }
+      }
+    }
+    SegmentGrid { tolerance, cells }
+  }
+
+  /* Shapes other than `exclude` with a border segment within the tolerance of `point`, measured
+  in degrees on the plane, which is close enough at the scale of the tolerance */
+  fn shapes_near(&self, point: Point, exclude: usize) -> Vec<usize> {
+    let mut near = Vec::new();
+    let Some(candidates) = self.cells.get(&to_cell(point)) else {
+      return near;
+    };
+    for (index, segment) in candidates {
+      if *index != exclude
+        && !near.contains(index)
+        && segment_distance(point, segment.0, segment.1) <= self.tolerance
+      {
+        near.push(*index);
+      }
+    }
+    near
+  }
+}
+
+fn to_cell(point: Point) -> (i64, i64) {
+  (
+    (point[0] / CELL_SIZE).floor() as i64,
+    (point[1] / CELL_SIZE).floor() as i64,
+  )
+}
@@ -0,0 +1,155 @@
+use crate::{
+  geo_drawable::GeoPolygons,
+  projection::{MAX_LATITUDE, MAX_LONGITUDE},
+};
+
+type GeoRing = Vec<Vec<f64>>;
+
+const FULL_TURN: f64 = 2.0 * MAX_LONGITUDE;
+
+/*
+Splits polygons which cross the antimeridian (or use longitudes outside of -180..180) into
+pieces which each lie within -180..180, so that no edge jumps across the whole map.
+Rings which circle a pole are closed along that pole before splitting.
+*/
+pub fn split_polygons(polygons: GeoPolygons) -> GeoPolygons {
+  let mut split = Vec::new();
+  for polygon in polygons {
+    if polygon.iter().all(is_within_range) {
+      split.push(polygon);
+      continue;
+    }
+    split.extend(split_polygon(&polygon));
+  }
+  split
+}
+
+fn is_within_range(ring: &GeoRing) -> bool {
+  let in_range = |point: &Vec<f64>| point[0].abs() <= MAX_LONGITUDE;
+  ring.iter().all(in_range)
+    && ring
+      .windows(2)
+      .all(|pair| (pair[1][0] - pair[0][0]).abs() <= MAX_LONGITUDE)
+}
+
+fn split_polygon(polygon: &[GeoRing]) -> GeoPolygons {
+  let Some((outer, holes)) = polygon.split_first() else {
+    return Vec::new();
+  };
+  let outer = unwrap_ring(outer, None);
+  let (min, max) = longitude_range(&outer);
+  let reference = (min + max) / 2.0;
+  let holes: Vec<GeoRing> = holes
+    .iter()
+    .map(|hole| unwrap_ring(hole, Some(reference)))
+    .collect();
+  // every 360 degree window the unwrapped outer ring overlaps becomes its own polygon
+  let first_window = ((min + MAX_LONGITUDE) / FULL_TURN).floor() as i32;
+  let last_window = ((max + MAX_LONGITUDE) / FULL_TURN).ceil() as i32 - 1;
+  let mut pieces = Vec::new();
+  for window in first_window..=last_window {
+    let offset = window as f64 * FULL_TURN;
+    let (low, high) = (offset - MAX_LONGITUDE, offset + MAX_LONGITUDE);
+    let Some(piece) = clip_ring(&outer, low, high, offset) else {
+      continue;
+    };
+    let mut piece_polygon = vec![piece];
+    piece_polygon.extend(
+      holes
+        .iter()
+        .filter_map(|hole| clip_ring(hole, low, high, offset)),
+    );
+    pieces.push(piece_polygon);
+  }
+  pieces
+}
+
+/*
+Makes a ring's longitudes continuous, so consecutive points are never more than 180 degrees
+apart; the first point is moved to the 360 degree turn closest to `reference` (if given).
+A ring which circles a pole ends a full turn away from where it started, and is closed
+with an edge along that pole.
+*/
+fn unwrap_ring(ring: &GeoRing, reference: Option<f64>) -> GeoRing {
+  let mut unwrapped: GeoRing = Vec::with_capacity(ring.len());
+  for point in ring {
+    let longitude = match unwrapped.last() {
+      Some(previous) => nearest_turn(point[0], previous[0]),
+      None => nearest_turn(point[0], reference.unwrap_or(0.0)),
+    };
+    unwrapped.push(vec![longitude, point[1]]);
+  }
+  let (Some(first), Some(last)) = (unwrapped.first().cloned(), unwrapped.last().cloned()) else {
+    return unwrapped;
+  };
+  if (last[0] - first[0]).abs() > MAX_LONGITUDE {
+    let mean_latitude = ring.iter().map(|point| point[1]).sum::<f64>() / ring.len() as f64;
+    let pole = MAX_LATITUDE.copysign(mean_latitude);
+    unwrapped.push(vec![last[0], pole]);
+    unwrapped.push(vec![first[0], pole]);
+    unwrapped.push(first);
+  }
+  unwrapped
+}
+
+/* The longitude equivalent to `longitude` which is closest to `target` */
+fn nearest_turn(longitude: f64, target: f64) -> f64 {
+  longitude + ((target - longitude) / FULL_TURN).round() * FULL_TURN
+}
+
+fn longitude_range(ring: &GeoRing) -> (f64, f64) {
+  ring.iter().fold((f64::MAX, f64::MIN), |(min, max), point| {
+    (min.min(point[0]), max.max(point[0]))
+  })
+}
+
+/*
+Sutherland-Hodgman clipping of a closed ring to low <= longitude <= high, then shifted back by
+`offset`. Concave rings may gain zero-width edges along the clip line, which is harmless for
+drawing, triangulation and even-odd hit-testing. Returns None if nothing is left.
+*/
+fn clip_ring(ring: &GeoRing, low: f64, high: f64, offset: f64) -> Option<GeoRing> {
+  let clipped = clip_edge(ring, |longitude| longitude >= low, low);
+  let clipped = clip_edge(&clipped, |longitude| longitude <= high, high);
+  if clipped.len() < 3 {
+    return None;
+  }
+  let mut clipped: GeoRing = clipped
+    .into_iter()
+    .map(|point| {
+      vec![
+        (point[0] - offset).clamp(-MAX_LONGITUDE, MAX_LONGITUDE),
+        point[1],
+      ]
+    })
+    .collect();
+  // GeoJSON rings repeat their first point at the end
+  clipped.push(clipped[0].clone());
+  Some(clipped)
+}
+
+/* Clips against one vertical line, returning an open ring (without the repeated first point) */
+fn clip_edge(ring: &GeoRing, inside: impl Fn(f64) -> bool, line: f64) -> GeoRing {
+  let points = match ring.split_last() {
+    Some((last, rest)) if rest.first() == Some(last) => rest,
+    _ => ring.as_slice(),
+  };
+  let mut clipped = Vec::new();
+  for (i, current) in points.iter().enumerate() {
+    let previous = &points[(i + points.len() - 1) % points.len()];
+    let crossing = || {
+      let t = (line - previous[0]) / (current[0] - previous[0]);
+      vec![line, previous[1] + t * (current[1] - previous[1])]
+    };
+    match (inside(previous[0]), inside(current[0])) {
+      (true, true) => clipped.push(current.clone()),
+      (true, false) => clipped.push(crossing()),
+      (false, true) => {
+        clipped.push(crossing());
+        clipped.push(current.clone());
+      }
+      (false, false) => {}
+    }
+  }
+  clipped
+}
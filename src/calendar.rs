@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MONTH_NAMES: [&str; 12] = [
+  "January",
+  "February",
+  "March",
+  "April",
+  "May",
+  "June",
+  "July",
+  "August",
+  "September",
+  "October",
+  "November",
+  "December",
+];
+
+/* A date and hour in game, on the Gregorian calendar */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GameDate {
+  pub year: i32,
+  // 1-12
+  pub month: u8,
+  // 1-31
+  pub day: u8,
+  // 0-23
+  pub hour: u8,
+}
+
+impl GameDate {
+  pub const fn new(year: i32, month: u8, day: u8, hour: u8) -> GameDate {
+    GameDate {
+      year,
+      month,
+      day,
+      hour,
+    }
+  }
+
+  pub fn advance_hours(&mut self, hours: u32) {
+    for _ in 0..hours {
+      self.hour += 1;
+      if self.hour < 24 {
+        continue;
+      }
+      self.hour = 0;
+      self.day += 1;
+      if self.day <= days_in_month(self.year, self.month) {
+        continue;
+      }
+      self.day = 1;
+      self.month += 1;
+      if self.month > 12 {
+        self.month = 1;
+        self.year += 1;
+      }
+    }
+  }
+
+  /* Whether this is the first hour of a day */
+  pub fn is_new_day(&self) -> bool {
+    self.hour == 0
+  }
+
+  pub fn month_name(&self) -> &'static str {
+    MONTH_NAMES[(self.month as usize - 1) % 12]
+  }
+}
+
+impl fmt::Display for GameDate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} {} {}, {:02}:00",
+      self.day,
+      self.month_name(),
+      self.year,
+      self.hour
+    )
+  }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+  (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u8) -> u8 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
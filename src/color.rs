@@ -0,0 +1,45 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  pub a: u8,
+}
+
+impl Color {
+  pub const BLACK: Color = Color::rgb(0, 0, 0);
+  pub const WHITE: Color = Color::rgb(255, 255, 255);
+  pub const LIGHT_GRAY: Color = Color::rgb(225, 225, 225);
+  pub const GREEN: Color = Color::rgb(0, 255, 0);
+  pub const BLUE: Color = Color::rgb(0, 0, 255);
+
+  pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::rgba(r, g, b, 255)
+  }
+
+  pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+    Color { r, g, b, a }
+  }
+
+  /* Parses "#rrggbb" or "#rrggbbaa", with or without the leading # */
+  pub fn from_hex(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+      return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let a = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::rgba(channel(0)?, channel(2)?, channel(4)?, a))
+  }
+
+  /* Blends towards `other` by `amount` (0 is unchanged, 1 is `other`), keeping this alpha */
+  pub fn mix(&self, other: Color, amount: f32) -> Color {
+    let blend = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+    Color::rgba(
+      blend(self.r, other.r),
+      blend(self.g, other.g),
+      blend(self.b, other.b),
+      self.a,
+    )
+  }
+}
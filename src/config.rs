@@ -1,81 +1,502 @@
-use crate::errors::MapLoadError;
-use serde::Deserialize;
-use std::{collections::HashMap, env, error::Error, fs, path::Path};
+use crate::{
+  errors::MapLoadError, files::read_json, manifest::MapManifest, projection::ProjectionKind,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+  collections::HashMap,
+  env, fs, io,
+  path::{Path, PathBuf},
+};
+
+// the lowest layer of configuration, overridden by everything else
+const DEFAULT_CONFIG: &str = include_str!("../config.default.json");
+const CONFIG_FILE: &str = "config.json";
 
 pub struct Config {
   pub map: MapConfig,
   pub view: ViewConfig,
+  // the config file which settings changed in game are saved to, if there is one
+  pub save_path: Option<PathBuf>,
 }
 
 pub struct MapConfig {
+  // the map folder's name
   pub name: String,
+  pub dir: Box<Path>,
+  // from the manifest, if any
+  pub display_name: String,
+  pub description: Option<String>,
+  pub author: Option<String>,
   pub nations_path: Box<Path>,
   pub provinces_dir: Box<Path>,
+  // optional, with provinces for any nation without a file in `provinces_dir`
+  pub global_provinces_path: Box<Path>,
   pub province_mappings_path: Box<Path>,
+  // optional, mapping nation IDs to hex colors
+  pub colors_path: Box<Path>,
+  // optional, listing links between nations or provinces across the sea
+  pub sea_links_path: Box<Path>,
+  // compiled by `mapgame compile`, and used instead of the above when up to date
+  pub cache_path: Box<Path>,
+  // the nation GeoJSON properties holding each nation's name, ID and hex color (if any)
+  pub nation_name_property: String,
+  pub nation_id_property: String,
+  pub color_property: String,
+  // the global provinces' properties holding each one's nation ID (if there is one; otherwise
+  // provinces go to the nation containing them), name and ID
+  pub province_nation_property: Option<String>,
+  pub province_name_property: String,
+  pub province_id_property: Option<String>,
+  pub projection: ProjectionKind,
 }
-pub type MapConfigs = HashMap<String, MapConfig>;
+pub type MapConfigs = HashMap<String, Result<MapConfig, MapLoadError>>;
 
 pub struct ViewConfig {
   pub min_zoom: f32,
+  pub max_zoom: f32,
+  // in window pixels per second, for keyboard and edge scrolling
+  pub pan_speed: f32,
+  // in window pixels from the edge of the window
+  pub edge_scroll_margin: u32,
+  pub display: DisplayConfig,
 }
 
-pub fn get_config() -> Result<Config, Box<dyn Error>> {
-  let config = get_config_json()?;
-  let mut maps = get_available_maps(&config.maps_dir)?;
-  let map = maps.remove(&config.map_name).ok_or_else(|| MapLoadError {
-    reason: format!(
-      "failed to find map {} in dir {}",
-      config.map_name, config.maps_dir
-    ),
-  })?;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowMode {
+  #[default]
+  Windowed,
+  Fullscreen,
+  // a window without decorations covering the desktop
+  Borderless,
+}
+
+impl WindowMode {
+  pub fn next(self) -> WindowMode {
+    match self {
+      WindowMode::Windowed => WindowMode::Borderless,
+      WindowMode::Borderless => WindowMode::Fullscreen,
+      WindowMode::Fullscreen => WindowMode::Windowed,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+  // in pixels; borderless windows always cover the desktop
+  pub resolution: (u32, u32),
+  pub window_mode: WindowMode,
+  pub vsync: bool,
+  // in frames per second, or 0 for no limit; ignored with vsync
+  pub frame_limit: u32,
+  // the multisampling level, or 0 for none
+  pub antialiasing: u32,
+}
+
+impl Default for DisplayConfig {
+  fn default() -> Self {
+    DisplayConfig {
+      resolution: (1920, 1080),
+      window_mode: WindowMode::Windowed,
+      vsync: false,
+      frame_limit: 60,
+      antialiasing: 0,
+    }
+  }
+}
+
+/*
+Settings given on the command line or in the environment, each overriding the config file.
+Unset fields leave the layer below as it is
+*/
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+  // a config file to use instead of the one in the user's config directory
+  pub config_path: Option<PathBuf>,
+  pub map_name: Option<String>,
+  pub maps_dir: Option<String>,
+  pub window_size: Option<(u32, u32)>,
+  pub window_mode: Option<WindowMode>,
+}
+
+impl ConfigOverrides {
+  /* Reads the `MAPGAME_*` environment variables */
+  pub fn from_env() -> Result<ConfigOverrides, MapLoadError> {
+    let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let invalid = |name: &str, value: &str, expected: &str| MapLoadError::Config {
+      path: PathBuf::from(format!("${}", name)),
+      reason: format!("expected {}, got {:?}", expected, value),
+    };
+    let window_size = match var("MAPGAME_WINDOW_SIZE") {
+      Some(value) => Some(
+        parse_window_size(&value)
+          .ok_or_else(|| invalid("MAPGAME_WINDOW_SIZE", &value, "<width>x<height>"))?,
+      ),
+      None => None,
+    };
+    let window_mode = match var("MAPGAME_FULLSCREEN") {
+      Some(value) => match parse_bool(&value) {
+        Some(true) => Some(WindowMode::Fullscreen),
+        Some(false) => Some(WindowMode::Windowed),
+        None => return Err(invalid("MAPGAME_FULLSCREEN", &value, "true or false")),
+      },
+      None => None,
+    };
+    Ok(ConfigOverrides {
+      config_path: var("MAPGAME_CONFIG").map(PathBuf::from),
+      map_name: var("MAPGAME_MAP"),
+      maps_dir: var("MAPGAME_MAPS_DIR"),
+      window_size,
+      window_mode,
+    })
+  }
+
+  /* The config file keys these override (nested keys joined by dots), and their values */
+  fn values(&self) -> Vec<(&'static str, Value)> {
+    let mut values = Vec::new();
+    if let Some(map_name) = &self.map_name {
+      values.push(("map_name", Value::from(map_name.as_str())));
+    }
+    if let Some(maps_dir) = &self.maps_dir {
+      values.push(("maps_dir", Value::from(maps_dir.as_str())));
+    }
+    if let Some((width, height)) = self.window_size {
+      values.push(("display.resolution", Value::from(vec![width, height])));
+    }
+    if let Some(window_mode) = self.window_mode {
+      values.push(("display.window_mode", to_value(&window_mode)));
+    }
+    values
+  }
+}
+
+/* `1280x720` */
+pub fn parse_window_size(value: &str) -> Option<(u32, u32)> {
+  let (width, height) = value.split_once(['x', 'X'])?;
+  let size = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+  (size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+  match value.to_ascii_lowercase().as_str() {
+    "1" | "true" | "yes" | "on" => Some(true),
+    "0" | "false" | "no" | "off" => Some(false),
+    _ => None,
+  }
+}
+
+/*
+Where the user's config file lives: `$XDG_CONFIG_HOME/mapgame` (or `~/.config/mapgame`) on Linux,
+`~/Library/Application Support/mapgame` on macOS and `%APPDATA%\mapgame` on Windows
+*/
+pub fn user_config_dir() -> Option<PathBuf> {
+  let from_var = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+  let base = if cfg!(target_os = "windows") {
+    PathBuf::from(from_var("APPDATA")?)
+  } else if cfg!(target_os = "macos") {
+    PathBuf::from(from_var("HOME")?).join("Library/Application Support")
+  } else {
+    match from_var("XDG_CONFIG_HOME") {
+      Some(dir) => PathBuf::from(dir),
+      None => PathBuf::from(from_var("HOME")?).join(".config"),
+    }
+  };
+  Some(base.join("mapgame"))
+}
+
+pub fn get_config() -> Result<Config, MapLoadError> {
+  load_config(&ConfigOverrides::default())
+}
+
+/*
+Resolves the configuration from these layers, each overriding the ones before:
+1. the defaults, as in `config.default.json`
+2. the config file: `--config`, else `MAPGAME_CONFIG`, else `config.json` in `user_config_dir`
+3. the `MAPGAME_*` environment variables
+4. the given command line overrides
+*/
+pub fn load_config(overrides: &ConfigOverrides) -> Result<Config, MapLoadError> {
+  let ResolvedConfig {
+    config, save_path, ..
+  } = resolve_config(overrides)?;
+  // only the selected map is loaded, so a broken manifest elsewhere doesn't stop the game starting
+  let map_dir = Path::new(&config.maps_dir).join(&config.map_name);
+  if !map_dir.is_dir() {
+    return Err(MapLoadError::Config {
+      path: Path::new(&config.maps_dir).to_path_buf(),
+      reason: format!("failed to find map {}", config.map_name),
+    });
+  }
+  let mut map = MapConfig::load(&config.map_name, &map_dir)?;
+  if let Some(projection) = config.projections.get(&map.name) {
+    map.projection = *projection;
+  }
+  if let Some(color_property) = config.color_properties.get(&map.name) {
+    map.color_property = color_property.clone();
+  }
   Ok(Config {
     map,
     view: ViewConfig {
       min_zoom: config.min_zoom,
+      max_zoom: config.max_zoom,
+      pan_speed: config.pan_speed,
+      edge_scroll_margin: config.edge_scroll_margin,
+      display: config.display,
     },
+    save_path,
+  })
+}
+
+/*
+Saves the display settings which differ between `before` and `after` into the config file at
+`path`, keeping everything else in it. Unchanged settings aren't written, so that overrides from
+the command line or environment don't end up saved
+*/
+pub fn save_display_changes(
+  path: &Path,
+  before: &DisplayConfig,
+  after: &DisplayConfig,
+) -> Result<(), MapLoadError> {
+  let (Value::Object(before), Value::Object(after)) = (to_value(before), to_value(after)) else {
+    return Ok(());
+  };
+  let changes: Map<String, Value> = after
+    .into_iter()
+    .filter(|(key, value)| before.get(key) != Some(value))
+    .collect();
+  if changes.is_empty() {
+    return Ok(());
+  }
+  let mut value = if path.exists() {
+    read_json(path)?
+  } else {
+    Value::Object(Map::new())
+  };
+  merge(
+    &mut value,
+    Value::Object(Map::from_iter([(
+      "display".to_string(),
+      Value::Object(changes),
+    )])),
+  );
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|error| MapLoadError::io(dir, error))?;
+  }
+  let json = serde_json::to_string_pretty(&value).unwrap_or_default();
+  fs::write(path, json + "\n").map_err(|error| MapLoadError::io(path, error))
+}
+
+/* Config values are plain data, so this can't fail */
+fn to_value(value: &impl Serialize) -> Value {
+  serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/* The resolved configuration as JSON, preceded by a comment listing the layers it came from */
+pub fn describe_config(overrides: &ConfigOverrides) -> Result<String, MapLoadError> {
+  let resolved = resolve_config(overrides)?;
+  let mut description = String::from("# layers, lowest precedence first:\n");
+  for layer in &resolved.layers {
+    description += &format!("#   {}\n", layer);
+  }
+  let json =
+    serde_json::to_string_pretty(&resolved.config).map_err(|error| MapLoadError::Config {
+      path: PathBuf::from(CONFIG_FILE),
+      reason: error.to_string(),
+    })?;
+  Ok(description + &json)
+}
+
+struct ResolvedConfig {
+  config: ConfigJson,
+  // descriptions of where the settings came from
+  layers: Vec<String>,
+  // the config file used, or the user's one even if it doesn't exist yet
+  save_path: Option<PathBuf>,
+}
+
+fn resolve_config(overrides: &ConfigOverrides) -> Result<ResolvedConfig, MapLoadError> {
+  let defaults_path = Path::new("config.default.json");
+  let mut value: Value =
+    serde_json::from_str(DEFAULT_CONFIG).map_err(|error| MapLoadError::Parse {
+      path: defaults_path.to_path_buf(),
+      reason: error.to_string(),
+    })?;
+  let mut layers = vec!["defaults (config.default.json)".to_string()];
+  // where a setting which fails to deserialize most likely came from
+  let mut source_path = defaults_path.to_path_buf();
+
+  let env_overrides = ConfigOverrides::from_env()?;
+  let save_path = match (&overrides.config_path, &env_overrides.config_path) {
+    (Some(path), _) | (None, Some(path)) => Some(path.clone()),
+    (None, None) => user_config_dir().map(|dir| dir.join(CONFIG_FILE)),
+  };
+  if let Some(path) = save_path.as_ref().filter(|path| path.exists()) {
+    let file_value: Value = read_json(path)?;
+    merge(&mut value, file_value);
+    layers.push(format!("config file {}", path.display()));
+    source_path = path.clone();
+  } else if let Some(path) = overrides
+    .config_path
+    .as_ref()
+    .or(env_overrides.config_path.as_ref())
+  {
+    return Err(MapLoadError::io(
+      path,
+      io::Error::from(io::ErrorKind::NotFound),
+    ));
+  }
+
+  for (name, layer_overrides) in [("environment", &env_overrides), ("command line", overrides)] {
+    let values = layer_overrides.values();
+    if values.is_empty() {
+      continue;
+    }
+    let keys: Vec<&str> = values.iter().map(|(key, _)| *key).collect();
+    layers.push(format!("{}: {}", name, keys.join(", ")));
+    for (key, override_value) in values {
+      let nested = key.rsplit('.').fold(override_value, |value, key| {
+        Value::Object(Map::from_iter([(key.to_string(), value)]))
+      });
+      merge(&mut value, nested);
+    }
+  }
+
+  let config = serde_json::from_value(value).map_err(|error| MapLoadError::Config {
+    path: source_path,
+    reason: error.to_string(),
+  })?;
+  Ok(ResolvedConfig {
+    config,
+    layers,
+    save_path,
   })
 }
 
-#[derive(Deserialize)]
+/* Overrides `base` with `value`, key by key within objects so partial config files work */
+fn merge(base: &mut Value, value: Value) {
+  match (base, value) {
+    (Value::Object(base), Value::Object(value)) => {
+      for (key, value) in value {
+        match base.get_mut(&key) {
+          Some(base_value) => merge(base_value, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (base, value) => *base = value,
+  }
+}
+
+#[derive(Serialize, Deserialize)]
 struct ConfigJson {
   map_name: String,
   maps_dir: String,
   min_zoom: f32,
+  #[serde(default = "default_max_zoom")]
+  max_zoom: f32,
+  #[serde(default = "default_pan_speed")]
+  pan_speed: f32,
+  #[serde(default = "default_edge_scroll_margin")]
+  edge_scroll_margin: u32,
+  #[serde(default)]
+  display: DisplayConfig,
+  // by map name, overriding the projection in the map's manifest
+  #[serde(default)]
+  projections: HashMap<String, ProjectionKind>,
+  // by map name, overriding the color property in the map's manifest
+  #[serde(default)]
+  color_properties: HashMap<String, String>,
 }
 
-fn get_config_json() -> Result<ConfigJson, Box<dyn Error>> {
-  let current_dir = env::current_dir()?;
-  let current_path = current_dir.as_path();
-  let path = Path::join(current_path, "config.json");
-  let exists = path.try_exists().unwrap_or(false);
-  if !exists {
-    let default_path = Path::join(current_path, "config.default.json");
-    fs::copy(default_path, &path)?;
-  }
-  let json = fs::read_to_string(path)?;
-  Ok(serde_json::from_str(&json)?)
+fn default_max_zoom() -> f32 {
+  32.0
+}
+
+fn default_pan_speed() -> f32 {
+  800.0
+}
+
+fn default_edge_scroll_margin() -> u32 {
+  8
 }
 
 const NATIONS_FILE: &str = "nations.geojson";
 const PROVINCE_MAPPINGS_FILE: &str = "provinces.json";
 const PROVINCES_DIR: &str = "provinces";
+const GLOBAL_PROVINCES_FILE: &str = "provinces.geojson";
+const COLORS_FILE: &str = "colors.json";
+const SEA_LINKS_FILE: &str = "sea_links.json";
+const CACHE_FILE: &str = "map.cache";
+const DEFAULT_NATION_NAME_PROPERTY: &str = "name";
+const DEFAULT_NATION_ID_PROPERTY: &str = "id";
+const DEFAULT_COLOR_PROPERTY: &str = "color";
+const DEFAULT_PROVINCE_NAME_PROPERTY: &str = "name";
+
+impl MapConfig {
+  /* The map in the given folder, as described by its manifest if it has one */
+  pub fn load(name: &str, dir: &Path) -> Result<MapConfig, MapLoadError> {
+    let manifest = MapManifest::load(dir)?;
+    let files = manifest.files;
+    let file = |path: Option<PathBuf>, default: &str| -> Box<Path> {
+      dir
+        .join(path.as_deref().unwrap_or(Path::new(default)))
+        .into()
+    };
+    Ok(MapConfig {
+      name: name.to_string(),
+      dir: dir.into(),
+      display_name: manifest.name.unwrap_or_else(|| name.to_string()),
+      description: manifest.description,
+      author: manifest.author,
+      nations_path: file(files.nations, NATIONS_FILE),
+      province_mappings_path: file(files.province_mappings, PROVINCE_MAPPINGS_FILE),
+      provinces_dir: file(files.provinces, PROVINCES_DIR),
+      global_provinces_path: file(files.global_provinces, GLOBAL_PROVINCES_FILE),
+      colors_path: file(files.colors, COLORS_FILE),
+      sea_links_path: file(files.sea_links, SEA_LINKS_FILE),
+      cache_path: file(files.cache, CACHE_FILE),
+      nation_name_property: manifest
+        .nation_name_property
+        .unwrap_or_else(|| DEFAULT_NATION_NAME_PROPERTY.to_string()),
+      nation_id_property: manifest
+        .nation_id_property
+        .unwrap_or_else(|| DEFAULT_NATION_ID_PROPERTY.to_string()),
+      color_property: manifest
+        .color_property
+        .unwrap_or_else(|| DEFAULT_COLOR_PROPERTY.to_string()),
+      province_nation_property: manifest.province_nation_property,
+      province_name_property: manifest
+        .province_name_property
+        .unwrap_or_else(|| DEFAULT_PROVINCE_NAME_PROPERTY.to_string()),
+      province_id_property: manifest.province_id_property,
+      projection: manifest.projection.unwrap_or_default(),
+    })
+  }
+}
 
-fn get_available_maps(maps_dir: &str) -> Result<MapConfigs, Box<dyn Error>> {
+/* Every folder in `maps_dir`, by folder name, each loaded separately so one broken map doesn't hide
+the others */
+pub fn get_available_maps(maps_dir: &str) -> Result<MapConfigs, MapLoadError> {
   let mut maps = HashMap::new();
-  let entries = fs::read_dir(maps_dir)?;
+  let to_io_error = |error| MapLoadError::io(Path::new(maps_dir), error);
+  let entries = fs::read_dir(maps_dir).map_err(to_io_error)?;
   for entry in entries {
-    let name = entry?.file_name().into_string().or_else(|s| {
-      Err(MapLoadError {
+    let entry = entry.map_err(to_io_error)?;
+    if !entry.file_type().map_err(to_io_error)?.is_dir() {
+      continue;
+    }
+    let name = entry
+      .file_name()
+      .into_string()
+      .map_err(|s| MapLoadError::Config {
+        path: Path::new(maps_dir).to_path_buf(),
         reason: format!("failed to convert map file name to string: {:?}", s),
-      })
-    })?;
-    let base_path = Path::new(maps_dir).join(&name);
-    let map = MapConfig {
-      name: name.clone(),
-      nations_path: base_path.join(NATIONS_FILE).into(),
-      province_mappings_path: base_path.join(PROVINCE_MAPPINGS_FILE).into(),
-      provinces_dir: base_path.join(PROVINCES_DIR).into(),
-    };
+      })?;
+    let map = MapConfig::load(&name, &entry.path());
     maps.insert(name, map);
   }
   Ok(maps)
@@ -0,0 +1,139 @@
+use crate::{
+  geo::{Point, PointKey, ring_area, ring_contains, ring_points, to_key},
+  geo_drawable::GeoPolygons,
+};
+use std::collections::HashMap;
+
+// in square degrees; rings left smaller than this (slivers where borders almost meet) are dropped
+const MIN_RING_AREA: f64 = 1e-9;
+
+/*
+Merges shapes into one, removing the borders between them. Borders count as shared where both
+shapes have the same points (as provinces cut from the same data do), and are cancelled out edge by
+edge; the remaining edges are chained back into rings. Edges which only nearly meet stay as thin
+slivers, and those with no real area are dropped.
+*/
+pub fn dissolve(shapes: &[&GeoPolygons]) -> GeoPolygons {
+  let mut points: HashMap<PointKey, Point> = HashMap::new();
+  let mut edges: Vec<(PointKey, PointKey)> = Vec::new();
+  for polygon in shapes.iter().copied().flatten() {
+    for (index, ring) in polygon.iter().enumerate() {
+      let mut ring = ring_points(ring);
+      // outer rings anticlockwise and holes clockwise, so shared edges run opposite ways
+      if (ring_area(&ring) > 0.0) != (index == 0) {
+        ring.reverse();
+      }
+      for (i, point) in ring.iter().enumerate() {
+        let next = ring[(i + 1) % ring.len()];
+        let (a, b) = (to_key(point), to_key(&next));
+        if a != b {
+          points.insert(a, *point);
+          edges.push((a, b));
+        }
+      }
+    }
+  }
+  let remaining = cancel_shared_edges(&edges);
+  let rings = chain_rings(&remaining, &points);
+
+  let (outers, holes): (Vec<_>, Vec<_>) = rings
+    .into_iter()
+    .filter(|ring| ring_area(ring).abs() >= MIN_RING_AREA)
+    .partition(|ring| ring_area(ring) > 0.0);
+  let mut polygons: Vec<Vec<Vec<Point>>> = outers.into_iter().map(|outer| vec![outer]).collect();
+  for hole in holes {
+    // the middle of an edge, as the hole's points may touch its outer ring
+    let inside = midpoint(hole[0], hole[1]);
+    let outer = polygons
+      .iter_mut()
+      .filter(|polygon| ring_contains(&polygon[0], inside))
+      .min_by(|a, b| ring_area(&a[0]).total_cmp(&ring_area(&b[0])));
+    if let Some(polygon) = outer {
+      polygon.push(hole);
+    }
+  }
+  polygons
+    .into_iter()
+    .map(|polygon| polygon.into_iter().map(close_ring).collect())
+    .collect()
+}
+
+/* The edges left once each is cancelled out by one running the opposite way, in their original
+order */
+fn cancel_shared_edges(edges: &[(PointKey, PointKey)]) -> Vec<(PointKey, PointKey)> {
+  let mut counts: HashMap<(PointKey, PointKey), usize> = HashMap::new();
+  for (a, b) in edges {
+    match counts.get_mut(&(*b, *a)).filter(|count| **count > 0) {
+      Some(reverse_count) => *reverse_count -= 1,
+      None => *counts.entry((*a, *b)).or_default() += 1,
+    }
+  }
+  edges
+    .iter()
+    .filter(|edge| match counts.get_mut(edge) {
+      Some(count) if *count > 0 => {
+        *count -= 1;
+        true
+      }
+      _ => false,
+    })
+    .copied()
+    .collect()
+}
+
+/*
+Follows edges end to start until each chain comes back around. Every point has as many edges
+leaving it as arriving, so each chain closes; where a point has several, any will do, which splits
+rings touching at a point into separate rings
+*/
+fn chain_rings(
+  edges: &[(PointKey, PointKey)],
+  points: &HashMap<PointKey, Point>,
+) -> Vec<Vec<Point>> {
+  let mut outgoing: HashMap<PointKey, Vec<usize>> = HashMap::new();
+  for (index, (a, _)) in edges.iter().enumerate().rev() {
+    outgoing.entry(*a).or_default().push(index);
+  }
+  let mut used = vec![false; edges.len()];
+  let mut rings = Vec::new();
+  for start in 0..edges.len() {
+    if used[start] {
+      continue;
+    }
+    let mut ring = Vec::new();
+    let mut index = start;
+    loop {
+      used[index] = true;
+      let (a, b) = edges[index];
+      ring.push(points[&a]);
+      if b == edges[start].0 {
+        rings.push(ring);
+        break;
+      }
+      let next = outgoing.get_mut(&b).and_then(|indexes| {
+        while let Some(next) = indexes.pop() {
+          if !used[next] {
+            return Some(next);
+          }
+        }
+        None
+      });
+      match next {
+        Some(next) => index = next,
+        // only malformed input (e.g. unclosed rings) leaves a chain with nowhere to go
+        None => break,
+      }
+    }
+  }
+  rings
+}
+
+fn midpoint(a: Point, b: Point) -> Point {
+  [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0]
+}
+
+fn close_ring(ring: Vec<Point>) -> Vec<Vec<f64>> {
+  let mut closed: Vec<Vec<f64>> = ring.iter().map(|point| point.to_vec()).collect();
+  closed.push(ring[0].to_vec());
+  closed
+}
@@ -1,16 +1,167 @@
 use std::{
   error,
   fmt::{self, Formatter},
+  io,
+  path::{Path, PathBuf},
 };
 
-#[derive(Debug, Clone)]
-pub struct MapLoadError {
-  pub reason: String,
+/* Where a feature is in a map's files, so errors can point at it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureRef {
+  pub path: PathBuf,
+  // the feature's position in its file's feature collection
+  pub index: usize,
+  // unknown if the feature's ID is what's missing
+  pub id: Option<String>,
+}
+
+impl FeatureRef {
+  pub fn new(path: &Path, index: usize) -> FeatureRef {
+    FeatureRef {
+      path: path.to_path_buf(),
+      index,
+      id: None,
+    }
+  }
+
+  pub fn with_id(self, id: &str) -> FeatureRef {
+    FeatureRef {
+      id: Some(id.to_string()),
+      ..self
+    }
+  }
+}
+
+impl fmt::Display for FeatureRef {
+  fn fmt<'a>(&self, f: &mut Formatter<'a>) -> fmt::Result {
+    match &self.id {
+      Some(id) => write!(f, "#{} ({})", self.index, id),
+      None => write!(f, "#{}", self.index),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum MapLoadError {
+  // config.json, or the map it names
+  Config {
+    path: PathBuf,
+    reason: String,
+  },
+  Io {
+    path: PathBuf,
+    error: io::Error,
+  },
+  // invalid JSON or GeoJSON
+  Parse {
+    path: PathBuf,
+    reason: String,
+  },
+  MissingProperty {
+    feature: FeatureRef,
+    property: String,
+  },
+  InvalidProperty {
+    feature: FeatureRef,
+    property: String,
+    reason: String,
+  },
+  BadGeometry {
+    feature: FeatureRef,
+    reason: String,
+  },
+  // an entry in provinces.json, colors.json or sea_links.json, by its key
+  Mapping {
+    path: PathBuf,
+    key: String,
+    reason: String,
+  },
+  Cache {
+    path: PathBuf,
+    reason: String,
+  },
+  // a saved game which can't be read, or is for another map
+  Save {
+    path: PathBuf,
+    reason: String,
+  },
+}
+
+impl MapLoadError {
+  pub fn io(path: &Path, error: io::Error) -> MapLoadError {
+    MapLoadError::Io {
+      path: path.to_path_buf(),
+      error,
+    }
+  }
+
+  /* The file the error is in */
+  pub fn path(&self) -> &Path {
+    match self {
+      MapLoadError::Config { path, .. }
+      | MapLoadError::Io { path, .. }
+      | MapLoadError::Parse { path, .. }
+      | MapLoadError::Mapping { path, .. }
+      | MapLoadError::Cache { path, .. }
+      | MapLoadError::Save { path, .. } => path,
+      MapLoadError::MissingProperty { feature, .. }
+      | MapLoadError::InvalidProperty { feature, .. }
+      | MapLoadError::BadGeometry { feature, .. } => &feature.path,
+    }
+  }
+
+  pub fn feature(&self) -> Option<&FeatureRef> {
+    match self {
+      MapLoadError::MissingProperty { feature, .. }
+      | MapLoadError::InvalidProperty { feature, .. }
+      | MapLoadError::BadGeometry { feature, .. } => Some(feature),
+      _ => None,
+    }
+  }
+
+  /* What went wrong, without the file and feature */
+  pub fn message(&self) -> String {
+    match self {
+      MapLoadError::Config { reason, .. } => format!("invalid config: {}", reason),
+      MapLoadError::Io { error, .. } => error.to_string(),
+      MapLoadError::Parse { reason, .. } => format!("failed to parse: {}", reason),
+      MapLoadError::MissingProperty { property, .. } => {
+        format!("missing property {:?}", property)
+      }
+      MapLoadError::InvalidProperty {
+        property, reason, ..
+      } => format!("invalid property {:?}: {}", property, reason),
+      MapLoadError::BadGeometry { reason, .. } => reason.clone(),
+      MapLoadError::Mapping { key, reason, .. } => format!("{}: {}", key, reason),
+      MapLoadError::Cache { reason, .. } | MapLoadError::Save { reason, .. } => reason.clone(),
+    }
+  }
+}
+
+impl error::Error for MapLoadError {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    match self {
+      MapLoadError::Io { error, .. } => Some(error),
+      _ => None,
+    }
+  }
 }
 
-impl error::Error for MapLoadError {}
 impl fmt::Display for MapLoadError {
   fn fmt<'a>(&self, f: &mut Formatter<'a>) -> fmt::Result {
-    write!(f, "failed to load map: {}", self.reason)
+    // files of any kind can fail to be read or parsed, and config errors say so in their message
+    let context = match self {
+      MapLoadError::Config { .. } | MapLoadError::Io { .. } | MapLoadError::Parse { .. } => None,
+      MapLoadError::Save { .. } => Some("invalid saved game"),
+      _ => Some("failed to load map"),
+    };
+    if let Some(context) = context {
+      write!(f, "{}: ", context)?;
+    }
+    write!(f, "{}", self.path().display())?;
+    if let Some(feature) = self.feature() {
+      write!(f, ": feature {}", feature)?;
+    }
+    write!(f, ": {}", self.message())
   }
 }
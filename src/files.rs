@@ -0,0 +1,26 @@
+use crate::errors::MapLoadError;
+use geojson::{FeatureCollection, GeoJson};
+use serde::de::DeserializeOwned;
+use std::{fs, path::Path};
+
+pub fn read_string(path: &Path) -> Result<String, MapLoadError> {
+  fs::read_to_string(path).map_err(|error| MapLoadError::io(path, error))
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, MapLoadError> {
+  serde_json::from_str(&read_string(path)?).map_err(|error| MapLoadError::Parse {
+    path: path.to_path_buf(),
+    reason: error.to_string(),
+  })
+}
+
+pub fn read_features(path: &Path) -> Result<FeatureCollection, MapLoadError> {
+  let to_parse_error = |error: geojson::Error| MapLoadError::Parse {
+    path: path.to_path_buf(),
+    reason: error.to_string(),
+  };
+  let geojson = read_string(path)?
+    .parse::<GeoJson>()
+    .map_err(to_parse_error)?;
+  FeatureCollection::try_from(geojson).map_err(to_parse_error)
+}
@@ -0,0 +1,63 @@
+/* Planar geometry on longitude/latitude rings, in degrees, before they're projected */
+pub type Point = [f64; 2];
+// the point's coordinates' bits, so shared points are matched exactly
+pub type PointKey = [u64; 2];
+
+pub fn to_key(point: &[f64]) -> PointKey {
+  [point[0].to_bits(), point[1].to_bits()]
+}
+
+/* GeoJSON rings repeat their first point at the end */
+pub fn open_ring(ring: &[Vec<f64>]) -> &[Vec<f64>] {
+  match ring.split_last() {
+    Some((last, rest)) if rest.first() == Some(last) => rest,
+    _ => ring,
+  }
+}
+
+/* The ring's points, without the repeated last point */
+pub fn ring_points(ring: &[Vec<f64>]) -> Vec<Point> {
+  open_ring(ring)
+    .iter()
+    .map(|point| [point[0], point[1]])
+    .collect()
+}
+
+/* Shoelace formula, positive for anticlockwise rings */
+pub fn ring_area(ring: &[Point]) -> f64 {
+  let mut area = 0.0;
+  for (i, a) in ring.iter().enumerate() {
+    let b = ring[(i + 1) % ring.len()];
+    area += a[0] * b[1] - b[0] * a[1];
+  }
+  area / 2.0
+}
+
+/* Raycasting, as `math::polygon_contains` */
+pub fn ring_contains(ring: &[Point], point: Point) -> bool {
+  let mut inside = false;
+  for (i, a) in ring.iter().enumerate() {
+    let b = ring[(i + 1) % ring.len()];
+    if (a[1] > point[1]) != (b[1] > point[1])
+      && point[0] < a[0] + (point[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+    {
+      inside = !inside;
+    }
+  }
+  inside
+}
+
+pub fn distance(a: Point, b: Point) -> f64 {
+  ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+/* From the point to the nearest point on the segment from `a` to `b` */
+pub fn segment_distance(point: Point, a: Point, b: Point) -> f64 {
+  let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+  let length_squared = dx * dx + dy * dy;
+  if length_squared == 0.0 {
+    return distance(point, a);
+  }
+  let t = (((point[0] - a[0]) * dx + (point[1] - a[1]) * dy) / length_squared).clamp(0.0, 1.0);
+  distance(point, [a[0] + t * dx, a[1] + t * dy])
+}
@@ -1,19 +1,55 @@
 use crate::{
-  errors::MapLoadError,
-  math::{polygon_area, polygon_contains},
+  antimeridian::split_polygons,
+  color::Color,
+  errors::{FeatureRef, MapLoadError},
+  label::LabelAnchor,
+  math::{Rect, Vector2f, polygon_contains, rings_area, rings_contain},
+  projection::{GeoPoint, Projection, project},
+  triangulate::triangulate,
+  world_map::LOAD_BOUNDS,
 };
 use geojson::{Feature, JsonValue, Value, feature::Id};
-use sfml::{
-  graphics::{Color, PrimitiveType, Rect, RenderStates, Vertex},
-  system::Vector2f,
-};
 
-pub type Bounds = Rect<f32>;
+pub type Bounds = Rect;
 pub type GeoPolygons = Vec<Vec<Vec<Vec<f64>>>>;
-pub type VectorPolygons = Vec<Vec<Vector2f>>;
+pub type VectorRing = Vec<Vector2f>;
+// the outer ring comes first, followed by any holes
+pub type VectorPolygon = Vec<VectorRing>;
+pub type VectorPolygons = Vec<VectorPolygon>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+  pub position: Vector2f,
+  pub color: Color,
+}
+
+/* A simplified copy of a drawable's polygons, drawn instead of them when zoomed out */
+#[derive(Debug)]
+pub struct DetailLevel {
+  // in degrees, how far the simplified borders may stray from the originals
+  pub tolerance: f64,
+  pub geo_polygons: GeoPolygons,
+  pub vector_polygons: VectorPolygons,
+  pub triangles: Vec<usize>,
+}
 
-pub const MAX_LATITUDE: f32 = 180.0;
-pub const MAX_LONGITUDE: f32 = 90.0;
+impl DetailLevel {
+  pub fn new(
+    tolerance: f64,
+    geo_polygons: GeoPolygons,
+    world_bounds: &Bounds,
+    projection: &dyn Projection,
+  ) -> DetailLevel {
+    let vector_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, projection, world_bounds);
+    let triangles = GeoDrawable::to_triangles(&vector_polygons);
+    DetailLevel {
+      tolerance,
+      geo_polygons,
+      vector_polygons,
+      triangles,
+    }
+  }
+}
 
 #[derive(Debug)]
 pub struct GeoDrawable {
@@ -21,46 +57,63 @@ pub struct GeoDrawable {
   pub name: String,
   pub geo_polygons: GeoPolygons,
   pub vector_polygons: VectorPolygons,
+  // as projected into `LOAD_BOUNDS`, so it stays the same on resize
   pub vector_total_area: f32,
+  // one per polygon, around its outer ring
   pub bounds: Vec<Bounds>,
-  pub cached_vertices: Vec<Vec<Vertex>>,
+  // triangle list indexing into `vector_polygons`' points, flattened in order
+  pub triangles: Vec<usize>,
+  // none leaves the fill out, showing whatever is underneath
+  pub fill_color: Option<Color>,
+  pub outline_color: Color,
+  // where the name goes, computed on resize
+  pub label: Option<LabelAnchor>,
+  // increasingly simplified versions, for detail levels 1 and up
+  pub details: Vec<DetailLevel>,
 }
 
 impl GeoDrawable {
+  /* `source` locates the feature in its file, for errors */
   pub fn new(
     feature: Feature,
+    source: FeatureRef,
     world_bounds: &Bounds,
+    projection: &dyn Projection,
     name_property: &str,
     id_property: Option<&str>,
   ) -> Result<Box<GeoDrawable>, MapLoadError> {
-    let id = if id_property.is_some() || feature.id.is_none() {
-      GeoDrawable::get_feature_property(&feature, id_property.unwrap_or("id"))
-    } else {
-      match feature.id.clone() {
-        Some(Id::String(id)) => Some(id.to_string()),
-        Some(Id::Number(id)) => Some(id.to_string()),
-        None => None,
+    let id = GeoDrawable::get_feature_id(&feature, id_property).ok_or_else(|| {
+      MapLoadError::MissingProperty {
+        feature: source.clone(),
+        property: id_property.unwrap_or("id").to_string(),
       }
-    }
-    .ok_or_else(|| MapLoadError {
-      reason: format!("GeoDrawable ID not found at property {:?}", id_property).to_string(),
     })?;
-    let name =
-      GeoDrawable::get_feature_property(&feature, name_property).ok_or_else(|| MapLoadError {
-        reason: format!("GeoDrawable name not found at property {}", name_property),
-      })?;
-    let geometry = feature.geometry.as_ref().ok_or_else(|| MapLoadError {
-      reason: format!("failed to load province geometry for '{}'", name),
+    let source = source.with_id(&id);
+    let name = GeoDrawable::get_feature_property(&feature, name_property).ok_or_else(|| {
+      MapLoadError::MissingProperty {
+        feature: source.clone(),
+        property: name_property.to_string(),
+      }
     })?;
+    let geometry = feature
+      .geometry
+      .as_ref()
+      .ok_or_else(|| MapLoadError::BadGeometry {
+        feature: source.clone(),
+        reason: "missing geometry".to_string(),
+      })?;
     let mut geo_polygons: GeoPolygons = Vec::new();
     if let Value::Polygon(polygon) = geometry.value.clone() {
       geo_polygons.push(polygon);
     } else if let Value::MultiPolygon(multi) = geometry.value.clone() {
       geo_polygons.extend(multi);
     }
-    let vector_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, world_bounds);
+    let mut geo_polygons = split_polygons(geo_polygons);
+    GeoDrawable::remove_stray_holes(&mut geo_polygons);
+    let vector_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, projection, world_bounds);
     let vector_total_area = GeoDrawable::to_vector_total_area(&vector_polygons);
     let bounds = GeoDrawable::to_bounds(&vector_polygons);
+    let triangles = GeoDrawable::to_triangles(&vector_polygons);
     Ok(Box::new(GeoDrawable {
       id,
       name,
@@ -68,10 +121,54 @@ impl GeoDrawable {
       vector_polygons,
       vector_total_area,
       bounds,
-      cached_vertices: Vec::new(),
+      triangles,
+      fill_color: None,
+      outline_color: Color::BLACK,
+      label: None,
+      details: Vec::new(),
     }))
   }
 
+  /* Rebuilds a drawable from geometry prepared by `new` (as stored in the map cache), skipping
+  antimeridian splitting and triangulation */
+  pub fn from_parts(
+    id: String,
+    name: String,
+    geo_polygons: GeoPolygons,
+    triangles: Vec<usize>,
+    bounds: Vec<Bounds>,
+    world_bounds: &Bounds,
+    projection: &dyn Projection,
+  ) -> Box<GeoDrawable> {
+    let vector_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, projection, world_bounds);
+    let vector_total_area = GeoDrawable::to_vector_total_area(&vector_polygons);
+    Box::new(GeoDrawable {
+      id,
+      name,
+      geo_polygons,
+      vector_polygons,
+      vector_total_area,
+      bounds,
+      triangles,
+      fill_color: None,
+      outline_color: Color::BLACK,
+      label: None,
+      details: Vec::new(),
+    })
+  }
+
+  /* The ID in `id_property`, or else the feature's own ID, or else the "id" property */
+  pub fn get_feature_id(feature: &Feature, id_property: Option<&str>) -> Option<String> {
+    if id_property.is_some() || feature.id.is_none() {
+      return GeoDrawable::get_feature_property(feature, id_property.unwrap_or("id"));
+    }
+    match feature.id.clone() {
+      Some(Id::String(id)) => Some(id.to_string()),
+      Some(Id::Number(id)) => Some(id.to_string()),
+      None => None,
+    }
+  }
+
   pub fn get_feature_property(feature: &Feature, key: &str) -> Option<String> {
     feature.property(key).and_then(|value| match value {
       JsonValue::String(str) => Some(str.clone()),
@@ -83,56 +180,134 @@ impl GeoDrawable {
     })
   }
 
-  pub fn on_resize(&mut self, bounds: &Bounds) {
-    self.vector_polygons = GeoDrawable::to_vector_polygons(&self.geo_polygons, bounds);
+  /* Resizing only scales and moves the projected points, so the triangulation still holds */
+  pub fn on_resize(&mut self, bounds: &Bounds, projection: &dyn Projection) {
+    self.vector_polygons = GeoDrawable::to_vector_polygons(&self.geo_polygons, projection, bounds);
     self.bounds = GeoDrawable::to_bounds(&self.vector_polygons);
+    self.label = LabelAnchor::new(&self.vector_polygons);
+    for detail in &mut self.details {
+      detail.vector_polygons =
+        GeoDrawable::to_vector_polygons(&detail.geo_polygons, projection, bounds);
+    }
   }
 
-  pub fn includes(&self, point: Vector2f) -> bool {
-    for bounds in &self.bounds {
-      if bounds.contains(point) {
-        for polygon in &self.vector_polygons {
-          if polygon_contains(point, polygon) {
-            return true;
-          }
-        }
+  /* Replaces the shape and its detail levels, such as when a nation's territory changes */
+  pub fn set_polygons(
+    &mut self,
+    geo_polygons: GeoPolygons,
+    details: Vec<DetailLevel>,
+    world_bounds: &Bounds,
+    projection: &dyn Projection,
+  ) {
+    let load_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, projection, &LOAD_BOUNDS);
+    self.vector_total_area = GeoDrawable::to_vector_total_area(&load_polygons);
+    self.vector_polygons = GeoDrawable::to_vector_polygons(&geo_polygons, projection, world_bounds);
+    self.bounds = GeoDrawable::to_bounds(&self.vector_polygons);
+    self.triangles = GeoDrawable::to_triangles(&self.vector_polygons);
+    self.label = LabelAnchor::new(&self.vector_polygons);
+    self.geo_polygons = geo_polygons;
+    self.details = details;
+  }
+
+  /* The outline at `level` (0 being full detail, or else the coarsest level there is) as a line
+  list, two points per edge */
+  pub fn outline_points(&self, level: usize) -> Vec<Vector2f> {
+    let (vector_polygons, _) = self.geometry(level);
+    let mut points = Vec::new();
+    for ring in vector_polygons.iter().flatten() {
+      // GeoJSON's repeated last point was dropped, so the last edge wraps around to the first point
+      for (i, point) in ring.iter().enumerate() {
+        points.push(*point);
+        points.push(ring[(i + 1) % ring.len()]);
       }
     }
-    false
+    points
+  }
+
+  /* The fill at `level` as a triangle list, as for `outline_points` */
+  pub fn fill_points(&self, level: usize) -> Vec<Vector2f> {
+    let (vector_polygons, triangles) = self.geometry(level);
+    let points: Vec<&Vector2f> = vector_polygons.iter().flatten().flatten().collect();
+    triangles.iter().map(|i| *points[*i]).collect()
+  }
+
+  fn geometry(&self, level: usize) -> (&VectorPolygons, &[usize]) {
+    match self.detail(level) {
+      Some(detail) => (&detail.vector_polygons, &detail.triangles),
+      None => (&self.vector_polygons, &self.triangles),
+    }
+  }
+
+  fn detail(&self, level: usize) -> Option<&DetailLevel> {
+    let index = level.checked_sub(1)?;
+    self.details.get(index).or(self.details.last())
+  }
+
+  pub fn includes(&self, point: Vector2f) -> bool {
+    (0..self.vector_polygons.len()).any(|index| self.polygon_includes(index, point))
+  }
+
+  /* Whether the polygon at `index` (as in `bounds`) includes `point` */
+  pub fn polygon_includes(&self, index: usize, point: Vector2f) -> bool {
+    self.bounds[index].contains(point) && rings_contain(point, &self.vector_polygons[index])
+  }
+
+  /* Drops inner rings which don't start inside their outer ring; some source data has these,
+  and they would otherwise punch holes in neighbouring polygons' hit-testing and meshes */
+  pub fn remove_stray_holes(geo_polygons: &mut GeoPolygons) {
+    let to_vector = |point: &Vec<f64>| Vector2f::new(point[0] as f32, point[1] as f32);
+    for polygon in geo_polygons {
+      let Some(outer) = polygon.first() else {
+        continue;
+      };
+      let outer: Vec<Vector2f> = outer.iter().map(to_vector).collect();
+      let mut is_outer = true;
+      polygon.retain(|ring| {
+        let keep = is_outer
+          || ring
+            .first()
+            .is_some_and(|p| polygon_contains(to_vector(p), &outer));
+        is_outer = false;
+        keep
+      });
+    }
   }
 
   pub fn to_vector_total_area(vector_polygons: &VectorPolygons) -> f32 {
     let mut total_area = 0.0;
     for polygon in vector_polygons {
-      total_area += polygon_area(polygon);
+      total_area += rings_area(polygon);
     }
     total_area
   }
 
-  pub fn to_vector_polygons(polygons: &GeoPolygons, bounds: &Bounds) -> Vec<Vec<Vector2f>> {
-    let mut vector_groups = Vec::new();
+  pub fn to_vector_polygons(
+    polygons: &GeoPolygons,
+    projection: &dyn Projection,
+    bounds: &Bounds,
+  ) -> VectorPolygons {
+    let mut vector_polygons = Vec::new();
     for polygon in polygons {
+      let mut vector_polygon = Vec::new();
       // see: https://stevage.github.io/geojson-spec/#section-3.1.6
       // the last point is a wraparound (identical to first) and can be ignored
       for linear_ring in polygon {
-        let mut vector_group = Vec::new();
+        let mut vector_ring = Vec::new();
         if let Some((_last, points)) = linear_ring.as_slice().split_last() {
           for point in points {
-            vector_group.push(GeoDrawable::to_vector(point, bounds));
+            vector_ring.push(GeoDrawable::to_vector(point, projection, bounds));
           }
         }
-        vector_groups.push(vector_group);
+        vector_polygon.push(vector_ring);
       }
+      vector_polygons.push(vector_polygon);
     }
-    vector_groups
+    vector_polygons
   }
 
-  pub fn to_vector(point: &Vec<f64>, bounds: &Bounds) -> Vector2f {
-    let latitude = point[0] as f32;
-    let longitude = point[1] as f32;
-    let x = (latitude + MAX_LATITUDE) * bounds.width / (2.0 * MAX_LATITUDE);
-    let y = bounds.height - ((longitude + MAX_LONGITUDE) * bounds.height / (2.0 * MAX_LONGITUDE));
-    Vector2f::new(x, y)
+  pub fn to_vector(point: &[f64], projection: &dyn Projection, bounds: &Bounds) -> Vector2f {
+    // GeoJSON positions are [longitude, latitude]
+    project(projection, GeoPoint::new(point[0], point[1]), bounds)
   }
 
   pub fn to_bounds(vector_polygons: &VectorPolygons) -> Vec<Bounds> {
@@ -142,7 +317,7 @@ impl GeoDrawable {
       let mut min_y = f32::MAX;
       let mut max_x = 0.0;
       let mut max_y = 0.0;
-      for vector in polygon {
+      for vector in polygon.first().into_iter().flatten() {
         if min_x > vector.x {
           min_x = vector.x;
         }
@@ -161,26 +336,21 @@ impl GeoDrawable {
     bounds
   }
 
-  pub fn update_cached_vertices(&mut self, color: Color) {
-    let zero = Vector2f::new(0.0, 0.0);
-    let mut cached_vertices = Vec::new();
-    for vectors in &self.vector_polygons {
-      let mut vertices = Vec::new();
-      for vector in vectors {
-        vertices.push(Vertex::new(*vector, color, zero));
-      }
-      cached_vertices.push(vertices);
+  /* Triangulates each polygon's outer ring together with its holes */
+  pub fn to_triangles(vector_polygons: &VectorPolygons) -> Vec<usize> {
+    let mut triangles = Vec::new();
+    let mut offset = 0;
+    for polygon in vector_polygons {
+      let rings: Vec<&[Vector2f]> = polygon.iter().map(|ring| ring.as_slice()).collect();
+      triangles.extend(triangulate(&rings).into_iter().map(|i| i + offset));
+      offset += rings.iter().map(|ring| ring.len()).sum::<usize>();
     }
-    self.cached_vertices = cached_vertices;
+    triangles
   }
 
-  pub fn draw(
-    &self,
-    target: &mut dyn sfml::graphics::RenderTarget,
-    states: &RenderStates<'_, '_, '_>,
-  ) {
-    for vertices in &self.cached_vertices {
-      target.draw_primitives(vertices, PrimitiveType::LINE_STRIP, states);
-    }
+  /* Only the colors change, so the projected geometry and triangulation are left alone */
+  pub fn set_colors(&mut self, fill_color: Option<Color>, outline_color: Color) {
+    self.fill_color = fill_color;
+    self.outline_color = outline_color;
   }
 }
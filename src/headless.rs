@@ -0,0 +1,252 @@
+use crate::{
+  calendar::GameDate,
+  config::Config,
+  errors::MapLoadError,
+  files::read_json,
+  nation::Nation,
+  player::Player,
+  simulation::{GameState, tick},
+  world_map::WorldMap,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::PathBuf};
+
+// a year of hourly ticks
+pub const DEFAULT_TICKS: u64 = 24 * 365;
+
+/* How to run a game without a window, from the command line */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadlessOptions {
+  // overrides the script's
+  pub ticks: Option<u64>,
+  pub script: Option<PathBuf>,
+  // added after the script's players
+  pub ai_players: usize,
+  // where to write the summary, instead of standard output
+  pub output: Option<PathBuf>,
+}
+
+/* A headless game's players and length, as read from a JSON file */
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Script {
+  pub ticks: Option<u64>,
+  pub players: Vec<ScriptedPlayer>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptedPlayer {
+  // played by the AI instead of following `actions`
+  pub ai: bool,
+  pub actions: Vec<ScriptedAction>,
+}
+
+/* An action, taken just before the given tick runs */
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedAction {
+  pub tick: u64,
+  #[serde(flatten)]
+  pub action: Action,
+}
+
+/* Everything a player can do */
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+  // by nation ID
+  PlayAs(String),
+}
+
+/* Decides a player's actions as the game runs; `players[index]` is the player being controlled */
+pub trait Controller {
+  fn actions(
+    &mut self,
+    index: usize,
+    players: &[Player],
+    state: &GameState,
+    world_map: &WorldMap,
+  ) -> Vec<Action>;
+
+  fn name(&self) -> &'static str;
+}
+
+/* Takes the actions from its script at their ticks */
+pub struct ScriptedController {
+  actions: Vec<ScriptedAction>,
+}
+
+impl ScriptedController {
+  pub fn new(actions: Vec<ScriptedAction>) -> ScriptedController {
+    ScriptedController { actions }
+  }
+}
+
+impl Controller for ScriptedController {
+  fn actions(
+    &mut self,
+    _index: usize,
+    _players: &[Player],
+    state: &GameState,
+    _world_map: &WorldMap,
+  ) -> Vec<Action> {
+    self
+      .actions
+      .iter()
+      .filter(|action| action.tick == state.ticks)
+      .map(|action| action.action.clone())
+      .collect()
+  }
+
+  fn name(&self) -> &'static str {
+    "scripted"
+  }
+}
+
+/* Plays as the nation with the most provinces (then the largest) which nobody else plays as */
+pub struct AiController;
+
+impl Controller for AiController {
+  fn actions(
+    &mut self,
+    index: usize,
+    players: &[Player],
+    _state: &GameState,
+    world_map: &WorldMap,
+  ) -> Vec<Action> {
+    if players[index].nation_id.is_some() {
+      return Vec::new();
+    }
+    let taken: HashSet<&String> = players
+      .iter()
+      .filter_map(|player| player.nation_id.as_ref())
+      .collect();
+    let largest = world_map
+      .nations()
+      .values()
+      .filter(|nation| !taken.contains(nation.id()))
+      .max_by(|a, b| {
+        let provinces = |nation: &Nation| world_map.ownership().owned_count(nation.id());
+        (provinces(a).cmp(&provinces(b)))
+          .then(a.area().total_cmp(&b.area()))
+          .then(b.id().cmp(a.id()))
+      });
+    largest
+      .map(|nation| Action::PlayAs(nation.id().clone()))
+      .into_iter()
+      .collect()
+  }
+
+  fn name(&self) -> &'static str {
+    "ai"
+  }
+}
+
+/* What a headless game ended with */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeadlessSummary {
+  pub map: String,
+  pub ticks: u64,
+  pub date: GameDate,
+  pub players: Vec<PlayerSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerSummary {
+  pub controller: String,
+  pub nation_id: Option<String>,
+  pub nation_name: Option<String>,
+  pub provinces: usize,
+}
+
+impl PlayerSummary {
+  pub fn new(player: &Player, controller: &dyn Controller, world_map: &WorldMap) -> PlayerSummary {
+    let nation = player
+      .nation_id
+      .as_ref()
+      .and_then(|id| world_map.nations().get(id));
+    PlayerSummary {
+      controller: controller.name().to_string(),
+      nation_id: player.nation_id.clone(),
+      nation_name: nation.map(|nation| nation.geo_drawable.name.clone()),
+      provinces: player
+        .nation_id
+        .as_ref()
+        .map_or(0, |id| world_map.ownership().owned_count(id)),
+    }
+  }
+}
+
+impl Script {
+  pub fn load(options: &HeadlessOptions) -> Result<Script, MapLoadError> {
+    match &options.script {
+      Some(path) => read_json(path),
+      None => Ok(Script::default()),
+    }
+  }
+}
+
+/* Loads the configured map and runs it for the scripted number of ticks, without a window */
+pub fn run_headless(
+  config: &Config,
+  options: &HeadlessOptions,
+) -> Result<HeadlessSummary, MapLoadError> {
+  let script = Script::load(options)?;
+  let world_map = WorldMap::new(&config.map)?;
+  let ticks = options.ticks.or(script.ticks).unwrap_or(DEFAULT_TICKS);
+  let mut controllers: Vec<Box<dyn Controller>> = Vec::new();
+  for player in script.players {
+    controllers.push(match player.ai {
+      true => Box::new(AiController),
+      false => Box::new(ScriptedController::new(player.actions)),
+    });
+  }
+  for _ in 0..options.ai_players {
+    controllers.push(Box::new(AiController));
+  }
+  let mut state = GameState::new();
+  let players = simulate(&mut state, &world_map, &mut controllers, ticks);
+  Ok(HeadlessSummary {
+    map: config.map.name.clone(),
+    ticks: state.ticks,
+    date: state.date,
+    players: players
+      .iter()
+      .zip(&controllers)
+      .map(|(player, controller)| PlayerSummary::new(player, controller.as_ref(), &world_map))
+      .collect(),
+  })
+}
+
+/* Runs `ticks` ticks with a player per controller, letting each act (in order) before each tick */
+pub fn simulate(
+  state: &mut GameState,
+  world_map: &WorldMap,
+  controllers: &mut [Box<dyn Controller>],
+  ticks: u64,
+) -> Vec<Player> {
+  let mut players: Vec<Player> = controllers.iter().map(|_| *Player::new()).collect();
+  for _ in 0..ticks {
+    for (index, controller) in controllers.iter_mut().enumerate() {
+      for action in controller.actions(index, &players, state, world_map) {
+        apply(&mut players, index, action, world_map);
+      }
+    }
+    tick(state);
+  }
+  players
+}
+
+/* Actions which can't be taken, such as playing as someone else's nation, are ignored */
+fn apply(players: &mut [Player], index: usize, action: Action, world_map: &WorldMap) {
+  match action {
+    Action::PlayAs(nation_id) => {
+      let taken = players
+        .iter()
+        .any(|player| player.nation_id.as_ref() == Some(&nation_id));
+      if !taken && world_map.nations().contains_key(&nation_id) {
+        players[index].nation_id = Some(nation_id);
+      }
+    }
+  }
+}
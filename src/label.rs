@@ -0,0 +1,355 @@
+use crate::{
+  geo_drawable::{VectorPolygon, VectorPolygons},
+  math::{Rect, Vector2f, rings_area, rings_contain},
+};
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+// a shape must be this much longer than it is wide before its label is rotated to follow it
+const MIN_ELONGATION: f32 = 2.0;
+// the pole search stops refining once it's within this fraction of the polygon's size
+const POLE_PRECISION: f32 = 0.01;
+const MAX_POLE_CELLS: usize = 1024;
+// the fraction of the available length and thickness a label may fill
+const LABEL_FILL: f32 = 0.85;
+// text is roughly this much taller than its font size, with room for its outline
+const LINE_HEIGHT: f32 = 1.2;
+// in pixels, kept clear around each label
+const LABEL_PADDING: f32 = 4.0;
+
+/* Where a shape's name can go: centered at `position`, along a line at `angle` degrees
+(clockwise, as y points down) with `length` to spare and `thickness` across it */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelAnchor {
+  pub position: Vector2f,
+  pub angle: f32,
+  pub length: f32,
+  pub thickness: f32,
+}
+
+impl LabelAnchor {
+  /* For the largest polygon, at its pole of inaccessibility (the interior point furthest from
+  its edges), or None if it has no area */
+  pub fn new(polygons: &VectorPolygons) -> Option<LabelAnchor> {
+    let polygon = polygons
+      .iter()
+      .filter(|polygon| polygon.first().is_some_and(|outer| outer.len() >= 3))
+      .max_by(|a, b| rings_area(a).total_cmp(&rings_area(b)))?;
+    let (position, radius) = pole_of_inaccessibility(polygon);
+    if radius <= 0.0 {
+      return None;
+    }
+    let angle = principal_angle(&polygon[0]);
+    let direction = Vector2f::new(angle.to_radians().cos(), angle.to_radians().sin());
+    // centered on the pole, so only as long as the nearer edge allows on both sides
+    let length = 2.0
+      * distance_to_edge(polygon, position, direction)
+        .min(distance_to_edge(polygon, position, -direction));
+    Some(LabelAnchor {
+      position,
+      angle,
+      length: length.max(2.0 * radius),
+      thickness: 2.0 * radius,
+    })
+  }
+
+  /* The anchor under a uniform scale and offset, such as from map coordinates to window pixels */
+  pub fn transform(&self, scale: f32, offset: Vector2f) -> LabelAnchor {
+    LabelAnchor {
+      position: self.position * scale + offset,
+      angle: self.angle,
+      length: self.length * scale,
+      thickness: self.thickness * scale,
+    }
+  }
+}
+
+/* A label to place, in window pixels */
+#[derive(Debug, Clone, Copy)]
+pub struct LabelCandidate {
+  pub anchor: LabelAnchor,
+  // the text's width at a font size of 1
+  pub text_width: f32,
+  pub min_size: f32,
+  pub max_size: f32,
+}
+
+/* A label which fits, by its index in the candidates */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedLabel {
+  pub index: usize,
+  pub position: Vector2f,
+  pub angle: f32,
+  pub size: f32,
+}
+
+/*
+Sizes each label to fit its anchor, then places them in order (so earlier candidates take
+priority), skipping those which are too small, off screen or would overlap a placed label
+*/
+pub fn layout_labels(candidates: &[LabelCandidate], viewport: Rect) -> Vec<PlacedLabel> {
+  let mut placed: Vec<(PlacedLabel, [Vector2f; 4])> = Vec::new();
+  for (index, candidate) in candidates.iter().enumerate() {
+    let anchor = &candidate.anchor;
+    if !viewport.contains(anchor.position) || candidate.text_width <= 0.0 {
+      continue;
+    }
+    let size = (LABEL_FILL * anchor.length / candidate.text_width)
+      .min(LABEL_FILL * anchor.thickness / LINE_HEIGHT)
+      .min(candidate.max_size);
+    if size < candidate.min_size {
+      continue;
+    }
+    let label = PlacedLabel {
+      index,
+      position: anchor.position,
+      angle: anchor.angle,
+      size,
+    };
+    let corners = label_corners(&label, candidate.text_width);
+    if placed
+      .iter()
+      .any(|(_, other)| rectangles_overlap(&corners, other))
+    {
+      continue;
+    }
+    placed.push((label, corners));
+  }
+  placed.into_iter().map(|(label, _)| label).collect()
+}
+
+/* The label's padded box, clockwise from its top left before rotation */
+fn label_corners(label: &PlacedLabel, text_width: f32) -> [Vector2f; 4] {
+  let half_width = (label.size * text_width + LABEL_PADDING) / 2.0;
+  let half_height = (label.size * LINE_HEIGHT + LABEL_PADDING) / 2.0;
+  let (sin, cos) = label.angle.to_radians().sin_cos();
+  [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y): (f32, f32)| {
+    let (x, y) = (x * half_width, y * half_height);
+    label.position + Vector2f::new(x * cos - y * sin, x * sin + y * cos)
+  })
+}
+
+/* Separating axis test between two convex quadrilaterals */
+fn rectangles_overlap(a: &[Vector2f; 4], b: &[Vector2f; 4]) -> bool {
+  for corners in [a, b] {
+    for i in 0..2 {
+      let edge = corners[i + 1] - corners[i];
+      let axis = Vector2f::new(-edge.y, edge.x);
+      let project = |corners: &[Vector2f; 4]| {
+        corners
+          .iter()
+          .map(|corner| corner.x * axis.x + corner.y * axis.y)
+          .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+          })
+      };
+      let ((a_min, a_max), (b_min, b_max)) = (project(a), project(b));
+      if a_max < b_min || b_max < a_min {
+        return false;
+      }
+    }
+  }
+  true
+}
+
+/*
+The interior point furthest from the polygon's edges, and that distance, found by refining a grid
+of cells most likely to hold it first (after Mapbox's polylabel)
+*/
+pub fn pole_of_inaccessibility(polygon: &VectorPolygon) -> (Vector2f, f32) {
+  let outer = &polygon[0];
+  let (mut min, mut max) = (outer[0], outer[0]);
+  for point in outer {
+    min = Vector2f::new(min.x.min(point.x), min.y.min(point.y));
+    max = Vector2f::new(max.x.max(point.x), max.y.max(point.y));
+  }
+  let size = max - min;
+  let cell_size = size.x.min(size.y);
+  if cell_size <= 0.0 {
+    return (min, 0.0);
+  }
+  let precision = size.x.max(size.y) * POLE_PRECISION;
+
+  let mut cells = BinaryHeap::new();
+  let half = cell_size / 2.0;
+  let mut x = min.x;
+  while x < max.x {
+    let mut y = min.y;
+    while y < max.y {
+      cells.push(Cell::new(Vector2f::new(x + half, y + half), half, polygon));
+      y += cell_size;
+    }
+    x += cell_size;
+  }
+  let mut best = Cell::new(centroid(outer), 0.0, polygon);
+  let center = Cell::new(min + size / 2.0, 0.0, polygon);
+  if center.distance > best.distance {
+    best = center;
+  }
+
+  let mut visited = 0;
+  while let Some(cell) = cells.pop() {
+    if cell.distance > best.distance {
+      best = cell;
+    }
+    visited += 1;
+    if cell.potential - best.distance <= precision || visited > MAX_POLE_CELLS {
+      continue;
+    }
+    let half = cell.half / 2.0;
+    for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+      let center = cell.center + Vector2f::new(dx * half, dy * half);
+      cells.push(Cell::new(center, half, polygon));
+    }
+  }
+  (best.center, best.distance.max(0.0))
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+  center: Vector2f,
+  half: f32,
+  // to the nearest edge, negative outside the polygon
+  distance: f32,
+  // the furthest any point in the cell could be from an edge
+  potential: f32,
+}
+
+impl Cell {
+  fn new(center: Vector2f, half: f32, polygon: &VectorPolygon) -> Cell {
+    let distance = signed_distance(center, polygon);
+    Cell {
+      center,
+      half,
+      distance,
+      potential: distance + half * std::f32::consts::SQRT_2,
+    }
+  }
+}
+
+impl PartialEq for Cell {
+  fn eq(&self, other: &Cell) -> bool {
+    self.potential == other.potential
+  }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+  fn partial_cmp(&self, other: &Cell) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Cell {
+  fn cmp(&self, other: &Cell) -> Ordering {
+    self.potential.total_cmp(&other.potential)
+  }
+}
+
+fn signed_distance(point: Vector2f, polygon: &VectorPolygon) -> f32 {
+  let mut nearest = f32::INFINITY;
+  for ring in polygon {
+    for (a, b) in ring_edges(ring) {
+      nearest = nearest.min(segment_distance(point, a, b));
+    }
+  }
+  if rings_contain(point, polygon) {
+    nearest
+  } else {
+    -nearest
+  }
+}
+
+fn segment_distance(point: Vector2f, a: Vector2f, b: Vector2f) -> f32 {
+  let edge = b - a;
+  let length_squared = edge.x * edge.x + edge.y * edge.y;
+  let t = if length_squared > 0.0 {
+    (((point.x - a.x) * edge.x + (point.y - a.y) * edge.y) / length_squared).clamp(0.0, 1.0)
+  } else {
+    0.0
+  };
+  length(point - (a + edge * t))
+}
+
+fn length(vector: Vector2f) -> f32 {
+  (vector.x * vector.x + vector.y * vector.y).sqrt()
+}
+
+/* Vector rings leave out the closing point */
+fn ring_edges(ring: &[Vector2f]) -> impl Iterator<Item = (Vector2f, Vector2f)> + '_ {
+  (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+/* The area centroid of a ring, or its first point if it has no area */
+fn centroid(ring: &[Vector2f]) -> Vector2f {
+  let (mut sum, mut area) = (Vector2f::new(0.0, 0.0), 0.0);
+  for (a, b) in ring_edges(ring) {
+    let cross = a.x * b.y - b.x * a.y;
+    sum = sum + (a + b) * cross;
+    area += cross;
+  }
+  if area == 0.0 {
+    ring[0]
+  } else {
+    sum / (3.0 * area)
+  }
+}
+
+/* The angle of the ring's longest axis (from the spread of its edges, weighted by length), or 0
+if the ring isn't elongated enough for it to matter */
+fn principal_angle(ring: &[Vector2f]) -> f32 {
+  let (mut total, mut mean) = (0.0, Vector2f::new(0.0, 0.0));
+  for (a, b) in ring_edges(ring) {
+    let weight = length(b - a);
+    total += weight;
+    mean = mean + (a + b) * (weight / 2.0);
+  }
+  if total <= 0.0 {
+    return 0.0;
+  }
+  let mean = mean / total;
+  let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+  for (a, b) in ring_edges(ring) {
+    let weight = length(b - a);
+    let offset = (a + b) / 2.0 - mean;
+    xx += weight * offset.x * offset.x;
+    yy += weight * offset.y * offset.y;
+    xy += weight * offset.x * offset.y;
+  }
+  // eigenvalues of the covariance matrix, the spread along and across the longest axis
+  let spread = (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+  let (along, across) = ((xx + yy) / 2.0 + spread, (xx + yy) / 2.0 - spread);
+  if along < MIN_ELONGATION * MIN_ELONGATION * across.max(0.0) {
+    return 0.0;
+  }
+  let angle = (0.5 * (2.0 * xy).atan2(xx - yy)).to_degrees();
+  // text reads left to right, so an axis pointing up-left is the same as one pointing down-right
+  if angle > 90.0 {
+    angle - 180.0
+  } else if angle < -90.0 {
+    angle + 180.0
+  } else {
+    angle
+  }
+}
+
+/* How far from `origin` (inside the polygon) along `direction` the first edge is */
+fn distance_to_edge(polygon: &VectorPolygon, origin: Vector2f, direction: Vector2f) -> f32 {
+  let mut nearest = f32::INFINITY;
+  for ring in polygon {
+    for (a, b) in ring_edges(ring) {
+      let edge = b - a;
+      let denominator = direction.x * edge.y - direction.y * edge.x;
+      if denominator == 0.0 {
+        continue;
+      }
+      let to_a = a - origin;
+      let t = (to_a.x * edge.y - to_a.y * edge.x) / denominator;
+      let u = (to_a.x * direction.y - to_a.y * direction.x) / denominator;
+      if t >= 0.0 && (0.0..=1.0).contains(&u) {
+        nearest = nearest.min(t);
+      }
+    }
+  }
+  if nearest.is_finite() { nearest } else { 0.0 }
+}
@@ -0,0 +1,29 @@
+pub mod adjacency;
+pub mod antimeridian;
+pub mod calendar;
+pub mod color;
+pub mod config;
+pub mod dissolve;
+pub mod errors;
+pub mod files;
+pub mod geo;
+pub mod geo_drawable;
+pub mod headless;
+pub mod label;
+pub mod manifest;
+pub mod map_cache;
+pub mod map_mesh;
+pub mod math;
+pub mod nation;
+pub mod ownership;
+pub mod palette;
+pub mod player;
+pub mod projection;
+pub mod province;
+pub mod save;
+pub mod simplify;
+pub mod simulation;
+pub mod spatial_index;
+pub mod triangulate;
+pub mod validate;
+pub mod world_map;
@@ -0,0 +1,50 @@
+use crate::{errors::MapLoadError, files::read_json, projection::ProjectionKind};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "map.json";
+
+/* A map folder's optional `map.json`, describing the map and where its files are. Everything is
+optional, defaulting to the layout of the maps shipped with the game */
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MapManifest {
+  // shown instead of the folder name
+  pub name: Option<String>,
+  pub description: Option<String>,
+  pub author: Option<String>,
+  pub projection: Option<ProjectionKind>,
+  // the nation GeoJSON properties holding each nation's name, ID and hex color
+  pub nation_name_property: Option<String>,
+  pub nation_id_property: Option<String>,
+  pub color_property: Option<String>,
+  // the global provinces file's properties holding each province's nation ID, name and ID
+  pub province_nation_property: Option<String>,
+  pub province_name_property: Option<String>,
+  pub province_id_property: Option<String>,
+  pub files: MapFiles,
+}
+
+/* Paths relative to the map folder */
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MapFiles {
+  pub nations: Option<PathBuf>,
+  pub provinces: Option<PathBuf>,
+  pub global_provinces: Option<PathBuf>,
+  pub province_mappings: Option<PathBuf>,
+  pub colors: Option<PathBuf>,
+  pub sea_links: Option<PathBuf>,
+  pub cache: Option<PathBuf>,
+}
+
+impl MapManifest {
+  /* The map folder's manifest, or the defaults if it has none */
+  pub fn load(map_dir: &Path) -> Result<MapManifest, MapLoadError> {
+    let path = map_dir.join(MANIFEST_FILE);
+    if !path.exists() {
+      return Ok(MapManifest::default());
+    }
+    read_json(&path)
+  }
+}
@@ -0,0 +1,527 @@
+use crate::{
+  adjacency::{Adjacency, Border, BorderKind},
+  color::Color,
+  config::MapConfig,
+  errors::MapLoadError,
+  geo_drawable::{Bounds, GeoDrawable, GeoPolygons},
+  math::Rect,
+  nation::{Nation, Nations},
+  projection::Projection,
+  province::{Province, ProvinceKey},
+  world_map::{LOAD_BOUNDS, MIN_NATION_AREA, MapData, WorldMap},
+};
+use std::{
+  collections::HashMap,
+  fmt::{self, Formatter},
+  fs,
+  path::{Path, PathBuf},
+  time::SystemTime,
+};
+
+// bump whenever the layout below changes, so old caches are rebuilt rather than misread
+pub const CACHE_VERSION: u32 = 1;
+const MAGIC: &[u8; 4] = b"MGMC";
+
+/* Compiles a map folder's sources into its cache file, returning the cache's path */
+pub fn compile(config: &MapConfig) -> Result<PathBuf, MapLoadError> {
+  let projection = config.projection.projection();
+  let map_data = WorldMap::load_map_data(config, projection.as_ref())?;
+  let mut writer = Writer::default();
+  writer.bytes(MAGIC);
+  writer.u32(CACHE_VERSION);
+  writer.u64(hash_sources(config)?);
+  write_map_data(&mut writer, &map_data);
+  fs::write(&config.cache_path, writer.0)
+    .map_err(|error| MapLoadError::io(&config.cache_path, error))?;
+  Ok(config.cache_path.to_path_buf())
+}
+
+/* Why the map cache couldn't be used, so the sources were loaded instead */
+#[derive(Debug)]
+pub enum CacheMiss {
+  // the map hasn't been compiled, which is usual while editing it
+  Missing,
+  // a source is newer than the cache, or its hash or the cache version has changed
+  Stale { path: PathBuf, map_name: String },
+  Unreadable(MapLoadError),
+}
+
+impl fmt::Display for CacheMiss {
+  fn fmt<'a>(&self, f: &mut Formatter<'a>) -> fmt::Result {
+    match self {
+      CacheMiss::Missing => write!(f, "there is no map cache"),
+      CacheMiss::Stale { path, map_name } => write!(
+        f,
+        "Map cache {:?} is out of date, run `mapgame compile {}` to rebuild it",
+        path, map_name
+      ),
+      CacheMiss::Unreadable(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+/* The cached map data, if there is a cache which is newer than every source and matches their
+hash, else why it can't be used */
+pub fn load_if_fresh(
+  config: &MapConfig,
+  projection: &dyn Projection,
+) -> Result<MapData, CacheMiss> {
+  if !config.cache_path.exists() {
+    return Err(CacheMiss::Missing);
+  }
+  match load(config, projection) {
+    Ok(Some(map_data)) => Ok(map_data),
+    Ok(None) => Err(CacheMiss::Stale {
+      path: config.cache_path.to_path_buf(),
+      map_name: config.name.clone(),
+    }),
+    Err(error) => Err(CacheMiss::Unreadable(error)),
+  }
+}
+
+fn load(config: &MapConfig, projection: &dyn Projection) -> Result<Option<MapData>, MapLoadError> {
+  let cache_modified = modified(&config.cache_path)?;
+  for path in source_paths(config)? {
+    if modified(&path)? > cache_modified {
+      return Ok(None);
+    }
+  }
+  let bytes =
+    fs::read(&config.cache_path).map_err(|error| MapLoadError::io(&config.cache_path, error))?;
+  let mut reader = Reader::new(&config.cache_path, &bytes);
+  if reader.bytes(MAGIC.len())? != MAGIC {
+    return Err(reader.error("not a map cache"));
+  }
+  if reader.u32()? != CACHE_VERSION || reader.u64()? != hash_sources(config)? {
+    return Ok(None);
+  }
+  Ok(Some(read_map_data(&mut reader, projection)?))
+}
+
+fn modified(path: &Path) -> Result<SystemTime, MapLoadError> {
+  fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .map_err(|error| MapLoadError::io(path, error))
+}
+
+/* Every file the map is loaded from, in a fixed order */
+fn source_paths(config: &MapConfig) -> Result<Vec<PathBuf>, MapLoadError> {
+  let mut paths = vec![
+    config.nations_path.to_path_buf(),
+    config.province_mappings_path.to_path_buf(),
+  ];
+  for optional_path in [
+    &config.global_provinces_path,
+    &config.colors_path,
+    &config.sea_links_path,
+  ] {
+    if optional_path.exists() {
+      paths.push(optional_path.to_path_buf());
+    }
+  }
+  if config.provinces_dir.exists() {
+    let mut province_paths = Vec::new();
+    let to_error = |error| MapLoadError::io(&config.provinces_dir, error);
+    for entry in fs::read_dir(&config.provinces_dir).map_err(to_error)? {
+      let path = entry.map_err(to_error)?.path();
+      if path
+        .extension()
+        .is_some_and(|extension| extension == "geojson")
+      {
+        province_paths.push(path);
+      }
+    }
+    province_paths.sort();
+    paths.extend(province_paths);
+  }
+  Ok(paths)
+}
+
+/* FNV-1a over the sources and the settings which change how they're loaded */
+fn hash_sources(config: &MapConfig) -> Result<u64, MapLoadError> {
+  let mut hasher = Hasher::default();
+  hasher.write(&CACHE_VERSION.to_le_bytes());
+  write_sources(&mut hasher, config)?;
+  Ok(hasher.0)
+}
+
+/* Identifies a version of the map, which saved games record. Unlike the cache's hash, it doesn't
+change with the cache's layout */
+pub fn map_hash(config: &MapConfig) -> Result<u64, MapLoadError> {
+  let mut hasher = Hasher::default();
+  write_sources(&mut hasher, config)?;
+  Ok(hasher.0)
+}
+
+fn write_sources(hasher: &mut Hasher, config: &MapConfig) -> Result<(), MapLoadError> {
+  hasher.write(format!("{:?}", config.projection).as_bytes());
+  hasher.write(config.nation_name_property.as_bytes());
+  hasher.write(config.nation_id_property.as_bytes());
+  hasher.write(config.color_property.as_bytes());
+  hasher.write(&MIN_NATION_AREA.to_le_bytes());
+  // only used with a global provinces file, so maps without one keep the hash their saves recorded
+  if config.global_provinces_path.exists() {
+    hasher.write(format!("{:?}", config.province_nation_property).as_bytes());
+    hasher.write(config.province_name_property.as_bytes());
+    hasher.write(format!("{:?}", config.province_id_property).as_bytes());
+  }
+  for path in source_paths(config)? {
+    hasher.write(relative_name(config, &path).as_bytes());
+    hasher.write(&fs::read(&path).map_err(|error| MapLoadError::io(&path, error))?);
+  }
+  Ok(())
+}
+
+/* A source's path within the map folder, so moving the whole folder keeps its cache valid */
+fn relative_name(config: &MapConfig, path: &Path) -> String {
+  path
+    .strip_prefix(&config.dir)
+    .unwrap_or(path)
+    .to_string_lossy()
+    .into_owned()
+}
+
+struct Hasher(u64);
+
+impl Default for Hasher {
+  fn default() -> Self {
+    Hasher(0xcbf2_9ce4_8422_2325)
+  }
+}
+
+impl Hasher {
+  fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 ^= *byte as u64;
+      self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+    // separates consecutive writes, so moving bytes between them changes the hash
+    self.0 = self.0.rotate_left(5) ^ bytes.len() as u64;
+  }
+}
+
+fn write_map_data(writer: &mut Writer, map_data: &MapData) {
+  let mut nation_ids: Vec<&String> = map_data.nations.keys().collect();
+  nation_ids.sort();
+  let mut province_keys = Vec::new();
+  writer.u32(nation_ids.len() as u32);
+  for nation_id in &nation_ids {
+    let nation = &map_data.nations[*nation_id];
+    write_geo_drawable(writer, &nation.geo_drawable);
+    match map_data.colors.get(*nation_id) {
+      Some(color) => {
+        writer.u8(1);
+        writer.bytes(&[color.r, color.g, color.b, color.a]);
+      }
+      None => writer.u8(0),
+    }
+    let Some(provinces) = nation.provinces.as_ref() else {
+      writer.u8(0);
+      continue;
+    };
+    writer.u8(1);
+    let mut province_ids: Vec<&String> = provinces.keys().collect();
+    province_ids.sort();
+    writer.u32(province_ids.len() as u32);
+    for province_id in province_ids {
+      write_geo_drawable(writer, &provinces[province_id].geo_drawable);
+      province_keys.push(ProvinceKey::new(nation_id, province_id));
+    }
+  }
+  let nation_indexes: HashMap<&String, u32> = nation_ids
+    .iter()
+    .enumerate()
+    .map(|(i, id)| (*id, i as u32))
+    .collect();
+  let province_indexes: HashMap<&ProvinceKey, u32> = province_keys
+    .iter()
+    .enumerate()
+    .map(|(i, key)| (key, i as u32))
+    .collect();
+  let nation_borders = map_data.adjacency.nations.iter();
+  write_borders(writer, nation_borders, &nation_indexes);
+  let province_borders = map_data.adjacency.provinces.iter();
+  write_borders(writer, province_borders, &province_indexes);
+}
+
+fn read_map_data(
+  reader: &mut Reader,
+  projection: &dyn Projection,
+) -> Result<MapData, MapLoadError> {
+  let mut nations: Nations = HashMap::new();
+  let mut colors = HashMap::new();
+  let mut nation_ids = Vec::new();
+  let mut province_keys = Vec::new();
+  for _ in 0..reader.u32()? {
+    let geo_drawable = read_geo_drawable(reader, projection)?;
+    let nation_id = geo_drawable.id.clone();
+    if reader.u8()? == 1 {
+      let rgba = reader.bytes(4)?;
+      let color = Color::rgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+      colors.insert(nation_id.clone(), color);
+    }
+    let provinces = if reader.u8()? == 1 {
+      let mut provinces = HashMap::new();
+      for _ in 0..reader.u32()? {
+        let province = Province::from_parts(read_geo_drawable(reader, projection)?);
+        province_keys.push(ProvinceKey::new(&nation_id, province.id()));
+        provinces.insert(province.id().clone(), province);
+      }
+      Some(provinces)
+    } else {
+      None
+    };
+    nations.insert(
+      nation_id.clone(),
+      Nation::from_parts(geo_drawable, provinces),
+    );
+    nation_ids.push(nation_id);
+  }
+  let mut adjacency = Adjacency::default();
+  for (a, b, border) in read_borders(reader)? {
+    let (a, b) = (
+      reader.lookup(&nation_ids, a)?,
+      reader.lookup(&nation_ids, b)?,
+    );
+    adjacency.nations.insert(a, b, border);
+  }
+  for (a, b, border) in read_borders(reader)? {
+    let (a, b) = (
+      reader.lookup(&province_keys, a)?,
+      reader.lookup(&province_keys, b)?,
+    );
+    adjacency.provinces.insert(a, b, border);
+  }
+  Ok(MapData {
+    nations,
+    colors,
+    adjacency,
+  })
+}
+
+fn write_geo_drawable(writer: &mut Writer, geo_drawable: &GeoDrawable) {
+  writer.str(&geo_drawable.id);
+  writer.str(&geo_drawable.name);
+  writer.u32(geo_drawable.geo_polygons.len() as u32);
+  for polygon in &geo_drawable.geo_polygons {
+    writer.u32(polygon.len() as u32);
+    for ring in polygon {
+      writer.u32(ring.len() as u32);
+      for point in ring {
+        writer.f64(point[0]);
+        writer.f64(point[1]);
+      }
+    }
+  }
+  writer.u32(geo_drawable.triangles.len() as u32);
+  for index in &geo_drawable.triangles {
+    writer.u32(*index as u32);
+  }
+  writer.u32(geo_drawable.bounds.len() as u32);
+  for bounds in &geo_drawable.bounds {
+    for value in [bounds.left, bounds.top, bounds.width, bounds.height] {
+      writer.f32(value);
+    }
+  }
+}
+
+fn read_geo_drawable(
+  reader: &mut Reader,
+  projection: &dyn Projection,
+) -> Result<Box<GeoDrawable>, MapLoadError> {
+  let id = reader.str()?;
+  let name = reader.str()?;
+  let mut geo_polygons: GeoPolygons = Vec::new();
+  for _ in 0..reader.u32()? {
+    let mut polygon = Vec::new();
+    for _ in 0..reader.u32()? {
+      let mut ring = Vec::new();
+      for _ in 0..reader.u32()? {
+        ring.push(vec![reader.f64()?, reader.f64()?]);
+      }
+      polygon.push(ring);
+    }
+    geo_polygons.push(polygon);
+  }
+  // triangles index the projected points, which leave out each ring's repeated last point
+  let point_count: usize = geo_polygons
+    .iter()
+    .flatten()
+    .map(|ring| ring.len().saturating_sub(1))
+    .sum();
+  let mut triangles = Vec::new();
+  for _ in 0..reader.u32()? {
+    let index = reader.u32()? as usize;
+    if index >= point_count {
+      return Err(reader.error(&format!(
+        "refers to missing point {} of shape {:?}",
+        index, id
+      )));
+    }
+    triangles.push(index);
+  }
+  let mut bounds: Vec<Bounds> = Vec::new();
+  for _ in 0..reader.u32()? {
+    let (left, top) = (reader.f32()?, reader.f32()?);
+    let (width, height) = (reader.f32()?, reader.f32()?);
+    bounds.push(Rect::new(left, top, width, height));
+  }
+  // one per polygon, which hit-testing relies on
+  if bounds.len() != geo_polygons.len() {
+    return Err(reader.error(&format!("has mismatched bounds for shape {:?}", id)));
+  }
+  Ok(GeoDrawable::from_parts(
+    id,
+    name,
+    geo_polygons,
+    triangles,
+    bounds,
+    &LOAD_BOUNDS,
+    projection,
+  ))
+}
+
+/* Writes each border once, as indexes into the shapes written before */
+fn write_borders<'a, K: Eq + std::hash::Hash + 'a>(
+  writer: &mut Writer,
+  borders: impl Iterator<Item = (&'a K, &'a K, &'a Border)>,
+  indexes: &HashMap<&K, u32>,
+) {
+  let mut indexed: Vec<(u32, u32, &Border)> = borders
+    .filter_map(|(a, b, border)| Some((*indexes.get(a)?, *indexes.get(b)?, border)))
+    .filter(|(a, b, _)| a < b)
+    .collect();
+  indexed.sort_by_key(|(a, b, _)| (*a, *b));
+  writer.u32(indexed.len() as u32);
+  for (a, b, border) in indexed {
+    writer.u32(a);
+    writer.u32(b);
+    writer.u8(match border.kind {
+      BorderKind::Land => 0,
+      BorderKind::Sea => 1,
+    });
+    writer.f64(border.length);
+  }
+}
+
+fn read_borders(reader: &mut Reader) -> Result<Vec<(u32, u32, Border)>, MapLoadError> {
+  let mut borders = Vec::new();
+  for _ in 0..reader.u32()? {
+    let (a, b) = (reader.u32()?, reader.u32()?);
+    let kind = match reader.u8()? {
+      0 => BorderKind::Land,
+      _ => BorderKind::Sea,
+    };
+    let length = reader.f64()?;
+    borders.push((a, b, Border { kind, length }));
+  }
+  Ok(borders)
+}
+
+/* Little-endian encoding of the cache's primitives */
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+  fn bytes(&mut self, bytes: &[u8]) {
+    self.0.extend_from_slice(bytes);
+  }
+
+  fn u8(&mut self, value: u8) {
+    self.0.push(value);
+  }
+
+  fn u32(&mut self, value: u32) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn u64(&mut self, value: u64) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn f32(&mut self, value: f32) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn f64(&mut self, value: f64) {
+    self.bytes(&value.to_le_bytes());
+  }
+
+  fn str(&mut self, value: &str) {
+    self.u32(value.len() as u32);
+    self.bytes(value.as_bytes());
+  }
+}
+
+struct Reader<'a> {
+  // for errors
+  path: &'a Path,
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn new(path: &'a Path, bytes: &'a [u8]) -> Reader<'a> {
+    Reader {
+      path,
+      bytes,
+      position: 0,
+    }
+  }
+
+  fn error(&self, reason: &str) -> MapLoadError {
+    MapLoadError::Cache {
+      path: self.path.to_path_buf(),
+      reason: reason.to_string(),
+    }
+  }
+
+  /* One of the shapes read so far, by its index in the cache */
+  fn lookup<'b, T>(&self, items: &'b [T], index: u32) -> Result<&'b T, MapLoadError> {
+    items
+      .get(index as usize)
+      .ok_or_else(|| self.error(&format!("refers to missing shape {}", index)))
+  }
+
+  fn bytes(&mut self, length: usize) -> Result<&'a [u8], MapLoadError> {
+    let end = self.position + length;
+    let bytes = self
+      .bytes
+      .get(self.position..end)
+      .ok_or_else(|| self.error("ended early"))?;
+    self.position = end;
+    Ok(bytes)
+  }
+
+  fn array<const N: usize>(&mut self) -> Result<[u8; N], MapLoadError> {
+    let mut array = [0; N];
+    array.copy_from_slice(self.bytes(N)?);
+    Ok(array)
+  }
+
+  fn u8(&mut self) -> Result<u8, MapLoadError> {
+    Ok(self.array::<1>()?[0])
+  }
+
+  fn u32(&mut self) -> Result<u32, MapLoadError> {
+    Ok(u32::from_le_bytes(self.array()?))
+  }
+
+  fn u64(&mut self) -> Result<u64, MapLoadError> {
+    Ok(u64::from_le_bytes(self.array()?))
+  }
+
+  fn f32(&mut self) -> Result<f32, MapLoadError> {
+    Ok(f32::from_le_bytes(self.array()?))
+  }
+
+  fn f64(&mut self) -> Result<f64, MapLoadError> {
+    Ok(f64::from_le_bytes(self.array()?))
+  }
+
+  fn str(&mut self) -> Result<String, MapLoadError> {
+    let length = self.u32()? as usize;
+    let bytes = self.bytes(length)?.to_vec();
+    String::from_utf8(bytes).map_err(|_| self.error("has an invalid string"))
+  }
+}
@@ -0,0 +1,159 @@
+use crate::{
+  color::Color,
+  geo_drawable::{GeoDrawable, Vertex},
+  math::Vector2f,
+  nation::Nation,
+  province::ProvinceKey,
+  world_map::WorldMap,
+};
+use std::{collections::HashMap, ops::Range};
+
+// unfilled provinces keep their place in the fill layer, so highlighting them is just a color change
+const NO_FILL: Color = Color::rgba(0, 0, 0, 0);
+
+/* One of the map's nations or provinces */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShapeKey {
+  Nation(String),
+  Province(ProvinceKey),
+}
+
+/* The mesh's layers, in drawing order: fills before outlines, so borders are never covered by a
+neighbour's fill, and nation borders over province borders */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+  NationFills,
+  ProvinceFills,
+  ProvinceOutlines,
+  NationOutlines,
+}
+
+impl Layer {
+  pub const ALL: [Layer; 4] = [
+    Layer::NationFills,
+    Layer::ProvinceFills,
+    Layer::ProvinceOutlines,
+    Layer::NationOutlines,
+  ];
+
+  /* Fills are triangle lists, outlines line lists */
+  pub fn is_fill(&self) -> bool {
+    matches!(self, Layer::NationFills | Layer::ProvinceFills)
+  }
+}
+
+/* Every shape in a layer, one after another */
+#[derive(Debug, Default)]
+pub struct LayerMesh {
+  pub vertices: Vec<Vertex>,
+  // where each shape's vertices are
+  ranges: HashMap<ShapeKey, Range<usize>>,
+}
+
+impl LayerMesh {
+  fn push(&mut self, key: ShapeKey, points: Vec<Vector2f>, color: Color) {
+    let start = self.vertices.len();
+    self.vertices.extend(
+      points
+        .into_iter()
+        .map(|position| Vertex { position, color }),
+    );
+    self.ranges.insert(key, start..self.vertices.len());
+  }
+
+  pub fn range(&self, key: &ShapeKey) -> Option<Range<usize>> {
+    self.ranges.get(key).cloned()
+  }
+}
+
+/*
+The whole map's geometry at one detail level, batched into a few large vertex arrays so it can be
+uploaded once and drawn in one call per layer. Highlighting and selection only recolor a shape's
+vertices in place, see `update_colors`; moving or resizing the map needs a new mesh.
+*/
+#[derive(Debug)]
+pub struct MapMesh {
+  pub level: usize,
+  // indexed as in `Layer::ALL`
+  layers: Vec<LayerMesh>,
+}
+
+impl MapMesh {
+  pub fn new(world_map: &WorldMap, level: usize) -> MapMesh {
+    let mut layers: Vec<LayerMesh> = Layer::ALL.iter().map(|_| LayerMesh::default()).collect();
+    // sorted, so the mesh is the same from run to run
+    let mut nations: Vec<(&String, &Box<Nation>)> = world_map.nations().iter().collect();
+    nations.sort_by_key(|(nation_id, _)| *nation_id);
+    for (nation_id, nation) in nations {
+      let key = ShapeKey::Nation(nation_id.clone());
+      MapMesh::push_shape(&mut layers, key, &nation.geo_drawable, level, false);
+      let mut provinces: Vec<_> = nation.provinces.iter().flatten().collect();
+      provinces.sort_by_key(|(province_id, _)| *province_id);
+      for (province_id, province) in provinces {
+        let key = ShapeKey::Province(ProvinceKey::new(nation_id, province_id));
+        MapMesh::push_shape(&mut layers, key, &province.geo_drawable, level, true);
+      }
+    }
+    MapMesh { level, layers }
+  }
+
+  fn push_shape(
+    layers: &mut [LayerMesh],
+    key: ShapeKey,
+    geo_drawable: &GeoDrawable,
+    level: usize,
+    is_province: bool,
+  ) {
+    let (fill, outline) = match is_province {
+      true => (Layer::ProvinceFills, Layer::ProvinceOutlines),
+      false => (Layer::NationFills, Layer::NationOutlines),
+    };
+    let fill_color = geo_drawable.fill_color.unwrap_or(NO_FILL);
+    layers[fill as usize].push(key.clone(), geo_drawable.fill_points(level), fill_color);
+    layers[outline as usize].push(
+      key,
+      geo_drawable.outline_points(level),
+      geo_drawable.outline_color,
+    );
+  }
+
+  pub fn layer(&self, layer: Layer) -> &LayerMesh {
+    &self.layers[layer as usize]
+  }
+
+  /* Recolors a shape's vertices to its current colors, returning the ranges changed in each layer */
+  pub fn update_colors(
+    &mut self,
+    world_map: &WorldMap,
+    key: &ShapeKey,
+  ) -> Vec<(Layer, Range<usize>)> {
+    let geo_drawable = match key {
+      ShapeKey::Nation(nation_id) => world_map
+        .nations()
+        .get(nation_id)
+        .map(|nation| nation.geo_drawable.as_ref()),
+      ShapeKey::Province(province_key) => world_map
+        .get_province(province_key)
+        .map(|province| province.geo_drawable.as_ref()),
+    };
+    let Some(geo_drawable) = geo_drawable else {
+      return Vec::new();
+    };
+    let mut changed = Vec::new();
+    for layer in Layer::ALL {
+      let mesh = &mut self.layers[layer as usize];
+      let Some(range) = mesh.range(key) else {
+        continue;
+      };
+      let color = match layer.is_fill() {
+        true => geo_drawable.fill_color.unwrap_or(NO_FILL),
+        false => geo_drawable.outline_color,
+      };
+      for vertex in &mut mesh.vertices[range.clone()] {
+        vertex.color = color;
+      }
+      changed.push((layer, range));
+    }
+    changed
+  }
+}
@@ -1,11 +1,93 @@
-use sfml::system::Vector2f;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector2f {
+  pub x: f32,
+  pub y: f32,
+}
+
+impl Vector2f {
+  pub const fn new(x: f32, y: f32) -> Vector2f {
+    Vector2f { x, y }
+  }
+}
+
+impl Add for Vector2f {
+  type Output = Vector2f;
+  fn add(self, rhs: Vector2f) -> Vector2f {
+    Vector2f::new(self.x + rhs.x, self.y + rhs.y)
+  }
+}
+
+impl Sub for Vector2f {
+  type Output = Vector2f;
+  fn sub(self, rhs: Vector2f) -> Vector2f {
+    Vector2f::new(self.x - rhs.x, self.y - rhs.y)
+  }
+}
+
+impl Mul<f32> for Vector2f {
+  type Output = Vector2f;
+  fn mul(self, rhs: f32) -> Vector2f {
+    Vector2f::new(self.x * rhs, self.y * rhs)
+  }
+}
+
+impl Div<f32> for Vector2f {
+  type Output = Vector2f;
+  fn div(self, rhs: f32) -> Vector2f {
+    Vector2f::new(self.x / rhs, self.y / rhs)
+  }
+}
+
+impl Neg for Vector2f {
+  type Output = Vector2f;
+  fn neg(self) -> Vector2f {
+    Vector2f::new(-self.x, -self.y)
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rect {
+  pub left: f32,
+  pub top: f32,
+  pub width: f32,
+  pub height: f32,
+}
+
+impl Rect {
+  pub const fn new(left: f32, top: f32, width: f32, height: f32) -> Rect {
+    Rect {
+      left,
+      top,
+      width,
+      height,
+    }
+  }
+
+  pub fn center(&self) -> Vector2f {
+    Vector2f::new(self.left + self.width / 2.0, self.top + self.height / 2.0)
+  }
+
+  pub fn size(&self) -> Vector2f {
+    Vector2f::new(self.width, self.height)
+  }
+
+  /* Right and bottom edges are exclusive, matching SFML's `Rect::contains` */
+  pub fn contains(&self, point: Vector2f) -> bool {
+    point.x >= self.left
+      && point.x < self.left + self.width
+      && point.y >= self.top
+      && point.y < self.top + self.height
+  }
+}
 
 /*
 clever raycasting
 https://stackoverflow.com/a/16391873/5850070
 https://wrf.ecse.rpi.edu/Research/Short_Notes/pnpoly.html
 */
-pub fn polygon_contains(point: Vector2f, polygon: &Vec<Vector2f>) -> bool {
+pub fn polygon_contains(point: Vector2f, polygon: &[Vector2f]) -> bool {
   let polygon_len = polygon.len();
   let mut i = 0;
   let mut j = polygon_len - 1;
@@ -24,7 +106,7 @@ pub fn polygon_contains(point: Vector2f, polygon: &Vec<Vector2f>) -> bool {
   inside
 }
 
-pub fn polygon_area(polygon: &Vec<Vector2f>) -> f32 {
+pub fn polygon_area(polygon: &[Vector2f]) -> f32 {
   let mut area = 0.0;
   let len = polygon.len();
   for i in 0..len {
@@ -34,3 +116,24 @@ pub fn polygon_area(polygon: &Vec<Vector2f>) -> f32 {
   }
   (area / 2.0).abs()
 }
+
+/* Even-odd containment over an outer ring and its holes */
+pub fn rings_contain(point: Vector2f, rings: &[Vec<Vector2f>]) -> bool {
+  let mut inside = false;
+  for ring in rings {
+    if polygon_contains(point, ring) {
+      inside = !inside;
+    }
+  }
+  inside
+}
+
+/* Area of an outer ring less the area of its holes */
+pub fn rings_area(rings: &[Vec<Vector2f>]) -> f32 {
+  match rings.split_first() {
+    Some((outer, holes)) => {
+      polygon_area(outer) - holes.iter().map(|hole| polygon_area(hole)).sum::<f32>()
+    }
+    None => 0.0,
+  }
+}
@@ -1,43 +1,110 @@
 use crate::{
+  color::Color,
   config::MapConfig,
+  errors::{FeatureRef, MapLoadError},
+  files::read_json,
   geo_drawable::{Bounds, GeoDrawable},
+  math::Vector2f,
+  projection::Projection,
   province::{Province, ProvinceMappings, Provinces},
 };
 use geojson::Feature;
-use sfml::{
-  graphics::{Color, Drawable},
-  system::Vector2f,
-};
-use std::{collections::HashMap, error::Error};
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct Nation {
   pub highlighted: bool,
   pub selected: bool,
+  // the base fill color, which highlighting and selection tint
+  pub color: Color,
   pub geo_drawable: Box<GeoDrawable>,
   pub provinces: Option<Provinces>,
 }
 pub type Nations = HashMap<String, Box<Nation>>;
+pub type NationColors = HashMap<String, Color>;
 
 impl Nation {
   pub fn new(
     feature: Feature,
+    source: FeatureRef,
     bounds: &Bounds,
+    projection: &dyn Projection,
     config: &MapConfig,
     province_mappings: &ProvinceMappings,
-  ) -> Result<Box<Nation>, Box<dyn Error>> {
-    let geo_drawable = GeoDrawable::new(feature, bounds, "name", Some("id"))?;
+  ) -> Result<Box<Nation>, MapLoadError> {
+    let geo_drawable = GeoDrawable::new(
+      feature,
+      source,
+      bounds,
+      projection,
+      &config.nation_name_property,
+      Some(&config.nation_id_property),
+    )?;
     let nation_id = geo_drawable.id.clone();
     let province_mapping = province_mappings.get(&nation_id);
-    let provinces = Province::load_nation(config, nation_id, province_mapping)?;
+    let provinces = Province::load_nation(config, projection, nation_id, province_mapping)?;
+    Ok(Nation::from_parts(geo_drawable, provinces))
+  }
+
+  pub fn from_parts(geo_drawable: Box<GeoDrawable>, provinces: Option<Provinces>) -> Box<Nation> {
     let mut nation = Box::new(Nation {
       geo_drawable,
       highlighted: false,
       selected: false,
+      color: Color::LIGHT_GRAY,
       provinces,
     });
-    nation.update_cached_vertices();
-    Ok(nation)
+    nation.update_colors();
+    nation
+  }
+
+  /* The color in the feature's configured color property, if it has one */
+  pub fn read_color(
+    feature: &Feature,
+    source: &FeatureRef,
+    config: &MapConfig,
+  ) -> Result<Option<Color>, MapLoadError> {
+    let Some(hex) = GeoDrawable::get_feature_property(feature, &config.color_property) else {
+      return Ok(None);
+    };
+    Color::from_hex(&hex)
+      .map(Some)
+      .ok_or_else(|| MapLoadError::InvalidProperty {
+        feature: match GeoDrawable::get_feature_id(feature, Some(&config.nation_id_property)) {
+          Some(id) => source.clone().with_id(&id),
+          None => source.clone(),
+        },
+        property: config.color_property.clone(),
+        reason: format!("{:?} is not a hex color", hex),
+      })
+  }
+
+  /* Colors from the map's colors file by nation ID, or none if the map doesn't have one */
+  pub fn load_colors(config: &MapConfig) -> Result<NationColors, MapLoadError> {
+    if !config.colors_path.exists() {
+      return Ok(HashMap::new());
+    }
+    let hex_colors: HashMap<String, String> = read_json(&config.colors_path)?;
+    let mut colors = HashMap::new();
+    for (nation_id, hex) in hex_colors {
+      let color = Color::from_hex(&hex).ok_or_else(|| MapLoadError::Mapping {
+        path: config.colors_path.to_path_buf(),
+        reason: format!("{:?} is not a hex color", hex),
+        key: nation_id.clone(),
+      })?;
+      colors.insert(nation_id, color);
+    }
+    Ok(colors)
+  }
+
+  pub fn set_color(&mut self, color: Color) {
+    self.color = color;
+    self.update_colors();
+    if let Some(provinces) = self.provinces.as_mut() {
+      for province in provinces.values_mut() {
+        province.set_color(color);
+      }
+    }
   }
 
   pub fn is_highlighted(&self) -> bool {
@@ -50,12 +117,12 @@ impl Nation {
 
   pub fn set_highlighted(&mut self, value: bool) {
     self.highlighted = value;
-    self.update_cached_vertices();
+    self.update_colors();
   }
 
   pub fn set_selected(&mut self, value: bool) {
     self.selected = value;
-    self.update_cached_vertices();
+    self.update_colors();
   }
 
   pub fn area(&self) -> f32 {
@@ -70,42 +137,25 @@ impl Nation {
     self.geo_drawable.includes(position)
   }
 
-  pub fn on_resize(&mut self, bounds: &Bounds) {
-    self.geo_drawable.on_resize(bounds);
-    self.update_cached_vertices();
-    self.provinces.as_mut().and_then(|provinces| {
-      for (_id, province) in provinces {
-        province.geo_drawable.on_resize(bounds);
-        province.update_cached_vertices();
+  pub fn on_resize(&mut self, bounds: &Bounds, projection: &dyn Projection) {
+    self.geo_drawable.on_resize(bounds, projection);
+    if let Some(provinces) = self.provinces.as_mut() {
+      for province in provinces.values_mut() {
+        province.geo_drawable.on_resize(bounds, projection);
       }
-      Some(())
-    });
+    }
   }
 
-  pub fn update_cached_vertices(&mut self) {
-    let color = if self.is_selected() {
-      Color::BLUE
+  pub fn update_colors(&mut self) {
+    let (fill_color, outline_color) = if self.is_selected() {
+      (self.color.mix(Color::BLUE, 0.25), Color::BLUE)
     } else if self.is_highlighted() {
-      Color::GREEN
+      (self.color.mix(Color::WHITE, 0.35), Color::GREEN)
     } else {
-      Color::BLACK
+      (self.color, Color::BLACK)
     };
-    self.geo_drawable.update_cached_vertices(color);
-  }
-}
-
-impl Drawable for Nation {
-  fn draw<'a: 'shader, 'texture, 'shader, 'shader_texture>(
-    &'a self,
-    target: &mut dyn sfml::graphics::RenderTarget,
-    states: &sfml::graphics::RenderStates<'texture, 'shader, 'shader_texture>,
-  ) {
-    self.provinces.as_ref().and_then(|provinces| {
-      for (_id, province) in provinces {
-        province.geo_drawable.draw(target, states);
-      }
-      Some(())
-    });
-    self.geo_drawable.draw(target, states);
+    self
+      .geo_drawable
+      .set_colors(Some(fill_color), outline_color);
   }
 }
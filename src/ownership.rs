@@ -0,0 +1,106 @@
+use crate::{nation::Nations, province::ProvinceKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/* Who holds a province: the nation it belongs to, and the one occupying it (usually the same) */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvinceControl {
+  pub owner: String,
+  pub controller: String,
+}
+
+impl ProvinceControl {
+  pub fn new(nation_id: &str) -> ProvinceControl {
+    ProvinceControl {
+      owner: nation_id.to_string(),
+      controller: nation_id.to_string(),
+    }
+  }
+
+  pub fn is_occupied(&self) -> bool {
+    self.owner != self.controller
+  }
+}
+
+/* A province which has changed hands since the map was loaded, as saved games store them */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvinceTransfer {
+  pub province: ProvinceKey,
+  #[serde(flatten)]
+  pub control: ProvinceControl,
+}
+
+/*
+Every province's owner and controller. Provinces start out owned and controlled by the nation whose
+file they were loaded from, which stays part of their key wherever they go
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ownership {
+  provinces: HashMap<ProvinceKey, ProvinceControl>,
+}
+
+impl Ownership {
+  pub fn new(nations: &Nations) -> Ownership {
+    let mut provinces = HashMap::new();
+    for (nation_id, nation) in nations {
+      for province_id in nation.provinces.iter().flatten().map(|(id, _)| id) {
+        let key = ProvinceKey::new(nation_id, province_id);
+        provinces.insert(key, ProvinceControl::new(nation_id));
+      }
+    }
+    Ownership { provinces }
+  }
+
+  pub fn get(&self, key: &ProvinceKey) -> Option<&ProvinceControl> {
+    self.provinces.get(key)
+  }
+
+  pub fn owner(&self, key: &ProvinceKey) -> Option<&String> {
+    self.get(key).map(|control| &control.owner)
+  }
+
+  pub fn controller(&self, key: &ProvinceKey) -> Option<&String> {
+    self.get(key).map(|control| &control.controller)
+  }
+
+  /* The provinces a nation owns, sorted */
+  pub fn owned_by(&self, nation_id: &str) -> Vec<&ProvinceKey> {
+    let mut keys: Vec<&ProvinceKey> = self
+      .provinces
+      .iter()
+      .filter(|(_, control)| control.owner == nation_id)
+      .map(|(key, _)| key)
+      .collect();
+    keys.sort();
+    keys
+  }
+
+  pub fn owned_count(&self, nation_id: &str) -> usize {
+    self
+      .provinces
+      .values()
+      .filter(|control| control.owner == nation_id)
+      .count()
+  }
+
+  /* Sets who holds a known province, returning who held it before */
+  pub fn set(&mut self, key: &ProvinceKey, control: ProvinceControl) -> Option<ProvinceControl> {
+    let current = self.provinces.get_mut(key)?;
+    Some(std::mem::replace(current, control))
+  }
+
+  /* Every province not held by the nation it was loaded from, sorted */
+  pub fn transfers(&self) -> Vec<ProvinceTransfer> {
+    let mut transfers: Vec<ProvinceTransfer> = self
+      .provinces
+      .iter()
+      .filter(|(key, control)| **control != ProvinceControl::new(&key.nation_id))
+      .map(|(key, control)| ProvinceTransfer {
+        province: key.clone(),
+        control: control.clone(),
+      })
+      .collect();
+    transfers.sort_by(|a, b| a.province.cmp(&b.province));
+    transfers
+  }
+}
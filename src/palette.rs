@@ -0,0 +1,44 @@
+use crate::color::Color;
+use std::{cmp::Reverse, collections::BTreeSet};
+
+// muted colors which stay readable under black borders and highlight tints
+pub const PALETTE: [Color; 8] = [
+  Color::rgb(231, 196, 150),
+  Color::rgb(170, 200, 160),
+  Color::rgb(160, 190, 220),
+  Color::rgb(220, 170, 170),
+  Color::rgb(200, 180, 220),
+  Color::rgb(235, 225, 150),
+  Color::rgb(160, 210, 200),
+  Color::rgb(210, 190, 160),
+];
+
+/*
+Fills in a palette color for each shape without one, avoiding the colors of adjacent shapes
+(by index pairs, lowest first). Shapes with the most neighbours are colored first, then in the
+given order, so the result only depends on the input order. Where every palette color is taken
+by a neighbour, the one used by the fewest neighbours is picked.
+*/
+pub fn assign_colors(colors: &[Option<Color>], adjacent: &BTreeSet<(usize, usize)>) -> Vec<Color> {
+  let mut neighbours = vec![Vec::new(); colors.len()];
+  for &(a, b) in adjacent {
+    neighbours[a].push(b);
+    neighbours[b].push(a);
+  }
+  let mut order: Vec<usize> = (0..colors.len()).filter(|i| colors[*i].is_none()).collect();
+  order.sort_by_key(|i| Reverse(neighbours[*i].len()));
+  let mut assigned = colors.to_vec();
+  for i in order {
+    let uses = |color: &&Color| {
+      neighbours[i]
+        .iter()
+        .filter(|neighbour| assigned[**neighbour] == Some(**color))
+        .count()
+    };
+    assigned[i] = PALETTE.iter().min_by_key(uses).copied();
+  }
+  assigned
+    .into_iter()
+    .map(|color| color.unwrap_or(PALETTE[0]))
+    .collect()
+}
@@ -1,3 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+/* Someone playing as a nation (once they've picked one), stored as it is in saved games */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Player {
   pub nation_id: Option<String>,
 }
@@ -0,0 +1,261 @@
+use crate::{geo_drawable::Bounds, math::Vector2f};
+use serde::{Deserialize, Serialize};
+use std::{
+  f64::consts::{FRAC_PI_2, FRAC_PI_4},
+  fmt::Debug,
+};
+
+pub const MAX_LONGITUDE: f64 = 180.0;
+pub const MAX_LATITUDE: f64 = 90.0;
+// mean radius, in kilometres
+pub const EARTH_RADIUS: f64 = 6371.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+  pub longitude: f64,
+  pub latitude: f64,
+}
+
+impl GeoPoint {
+  pub fn new(longitude: f64, latitude: f64) -> GeoPoint {
+    GeoPoint {
+      longitude,
+      latitude,
+    }
+  }
+
+  /* Great-circle distance in kilometres */
+  pub fn distance(&self, other: GeoPoint) -> f64 {
+    let (latitude1, latitude2) = (self.latitude.to_radians(), other.latitude.to_radians());
+    let half_latitude = (latitude2 - latitude1) / 2.0;
+    let half_longitude = (other.longitude - self.longitude).to_radians() / 2.0;
+    let a = half_latitude.sin().powi(2)
+      + latitude1.cos() * latitude2.cos() * half_longitude.sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+  }
+}
+
+/*
+A map projection between longitude/latitude (in degrees) and an unscaled plane where y points north.
+`project` and `unproject` fit that plane to the bounds the map is drawn in.
+*/
+pub trait Projection: Debug {
+  fn forward(&self, point: GeoPoint) -> (f64, f64);
+
+  /* Returns None for plane coordinates outside of the globe */
+  fn inverse(&self, x: f64, y: f64) -> Option<GeoPoint>;
+
+  /* The rectangle in the plane containing the whole globe, as (min x, min y, max x, max y) */
+  fn extent(&self) -> (f64, f64, f64, f64) {
+    let (max_x, _) = self.forward(GeoPoint::new(MAX_LONGITUDE, 0.0));
+    let (_, max_y) = self.forward(GeoPoint::new(0.0, MAX_LATITUDE));
+    (-max_x, -max_y, max_x, max_y)
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectionKind {
+  #[default]
+  Equirectangular,
+  Mercator,
+  Robinson,
+  EqualEarth,
+}
+
+impl ProjectionKind {
+  pub fn projection(&self) -> Box<dyn Projection> {
+    match self {
+      ProjectionKind::Equirectangular => Box::new(Equirectangular),
+      ProjectionKind::Mercator => Box::new(Mercator),
+      ProjectionKind::Robinson => Box::new(Robinson),
+      ProjectionKind::EqualEarth => Box::new(EqualEarth),
+    }
+  }
+}
+
+pub fn project(projection: &dyn Projection, point: GeoPoint, bounds: &Bounds) -> Vector2f {
+  let (min_x, min_y, max_x, max_y) = projection.extent();
+  let (x, y) = projection.forward(point);
+  Vector2f::new(
+    bounds.left + ((x - min_x) / (max_x - min_x)) as f32 * bounds.width,
+    bounds.top + ((max_y - y) / (max_y - min_y)) as f32 * bounds.height,
+  )
+}
+
+pub fn unproject(
+  projection: &dyn Projection,
+  position: Vector2f,
+  bounds: &Bounds,
+) -> Option<GeoPoint> {
+  let (min_x, min_y, max_x, max_y) = projection.extent();
+  let x = min_x + ((position.x - bounds.left) / bounds.width) as f64 * (max_x - min_x);
+  let y = max_y - ((position.y - bounds.top) / bounds.height) as f64 * (max_y - min_y);
+  projection.inverse(x, y)
+}
+
+fn is_on_globe(point: GeoPoint) -> bool {
+  point.longitude.abs() <= MAX_LONGITUDE && point.latitude.abs() <= MAX_LATITUDE
+}
+
+/* Plate carrée: longitude and latitude map directly onto x and y */
+#[derive(Debug)]
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+  fn forward(&self, point: GeoPoint) -> (f64, f64) {
+    (point.longitude, point.latitude)
+  }
+
+  fn inverse(&self, x: f64, y: f64) -> Option<GeoPoint> {
+    Some(GeoPoint::new(x, y)).filter(|point| is_on_globe(*point))
+  }
+}
+
+// the poles are infinitely far away in Mercator, so it is cut off where the map becomes square
+pub const MERCATOR_MAX_LATITUDE: f64 = 85.051_128_78;
+
+#[derive(Debug)]
+pub struct Mercator;
+
+impl Projection for Mercator {
+  fn forward(&self, point: GeoPoint) -> (f64, f64) {
+    let latitude = point
+      .latitude
+      .clamp(-MERCATOR_MAX_LATITUDE, MERCATOR_MAX_LATITUDE)
+      .to_radians();
+    let y = (FRAC_PI_4 + latitude / 2.0).tan().ln();
+    (point.longitude.to_radians(), y)
+  }
+
+  fn inverse(&self, x: f64, y: f64) -> Option<GeoPoint> {
+    let latitude = 2.0 * y.exp().atan() - FRAC_PI_2;
+    Some(GeoPoint::new(x.to_degrees(), latitude.to_degrees()))
+      .filter(|point| is_on_globe(*point) && point.latitude.abs() <= MERCATOR_MAX_LATITUDE)
+  }
+}
+
+// Robinson's table of (parallel length, distance from equator), every 5 degrees of latitude
+const ROBINSON_TABLE: [(f64, f64); 19] = [
+  (1.0000, 0.0000),
+  (0.9986, 0.0620),
+  (0.9954, 0.1240),
+  (0.9900, 0.1860),
+  (0.9822, 0.2480),
+  (0.9730, 0.3100),
+  (0.9600, 0.3720),
+  (0.9427, 0.4340),
+  (0.9216, 0.4958),
+  (0.8962, 0.5571),
+  (0.8679, 0.6176),
+  (0.8350, 0.6769),
+  (0.7986, 0.7346),
+  (0.7597, 0.7903),
+  (0.7186, 0.8435),
+  (0.6732, 0.8936),
+  (0.6213, 0.9394),
+  (0.5722, 0.9761),
+  (0.5322, 1.0000),
+];
+const ROBINSON_STEP: f64 = 5.0;
+const ROBINSON_X_SCALE: f64 = 0.8487;
+const ROBINSON_Y_SCALE: f64 = 1.3523;
+
+#[derive(Debug)]
+pub struct Robinson;
+
+impl Robinson {
+  /* Linearly interpolates the table at an absolute latitude in degrees */
+  fn interpolate(latitude: f64) -> (f64, f64) {
+    let position = (latitude.abs() / ROBINSON_STEP).min((ROBINSON_TABLE.len() - 1) as f64);
+    let index = (position.floor() as usize).min(ROBINSON_TABLE.len() - 2);
+    let fraction = position - index as f64;
+    let (x0, y0) = ROBINSON_TABLE[index];
+    let (x1, y1) = ROBINSON_TABLE[index + 1];
+    (x0 + (x1 - x0) * fraction, y0 + (y1 - y0) * fraction)
+  }
+}
+
+impl Projection for Robinson {
+  fn forward(&self, point: GeoPoint) -> (f64, f64) {
+    let (parallel, distance) = Robinson::interpolate(point.latitude);
+    let x = ROBINSON_X_SCALE * parallel * point.longitude.to_radians();
+    let y = ROBINSON_Y_SCALE * distance * point.latitude.signum();
+    (x, y)
+  }
+
+  fn inverse(&self, x: f64, y: f64) -> Option<GeoPoint> {
+    let distance = (y / ROBINSON_Y_SCALE).abs();
+    if distance > 1.0 {
+      return None;
+    }
+    let index = ROBINSON_TABLE
+      .windows(2)
+      .position(|pair| distance <= pair[1].1)
+      .unwrap_or(ROBINSON_TABLE.len() - 2);
+    let (_, y0) = ROBINSON_TABLE[index];
+    let (_, y1) = ROBINSON_TABLE[index + 1];
+    let fraction = (distance - y0) / (y1 - y0);
+    let latitude = (index as f64 + fraction) * ROBINSON_STEP * y.signum();
+    let (parallel, _) = Robinson::interpolate(latitude);
+    let longitude = (x / (ROBINSON_X_SCALE * parallel)).to_degrees();
+    Some(GeoPoint::new(longitude, latitude)).filter(|point| is_on_globe(*point))
+  }
+}
+
+// see: https://doi.org/10.1080/13658816.2018.1504949
+const EQUAL_EARTH_A1: f64 = 1.340264;
+const EQUAL_EARTH_A2: f64 = -0.081106;
+const EQUAL_EARTH_A3: f64 = 0.000893;
+const EQUAL_EARTH_A4: f64 = 0.003796;
+const EQUAL_EARTH_M: f64 = 0.866_025_403_784_438_6; // sqrt(3) / 2
+
+#[derive(Debug)]
+pub struct EqualEarth;
+
+impl EqualEarth {
+  fn y(theta: f64) -> f64 {
+    let theta2 = theta * theta;
+    let theta6 = theta2 * theta2 * theta2;
+    theta
+      * (EQUAL_EARTH_A1
+        + EQUAL_EARTH_A2 * theta2
+        + theta6 * (EQUAL_EARTH_A3 + EQUAL_EARTH_A4 * theta2))
+  }
+
+  /* Derivative of `y` with respect to theta */
+  fn dy(theta: f64) -> f64 {
+    let theta2 = theta * theta;
+    let theta6 = theta2 * theta2 * theta2;
+    EQUAL_EARTH_A1
+      + 3.0 * EQUAL_EARTH_A2 * theta2
+      + theta6 * (7.0 * EQUAL_EARTH_A3 + 9.0 * EQUAL_EARTH_A4 * theta2)
+  }
+}
+
+impl Projection for EqualEarth {
+  fn forward(&self, point: GeoPoint) -> (f64, f64) {
+    let theta = (EQUAL_EARTH_M * point.latitude.to_radians().sin()).asin();
+    let x = point.longitude.to_radians() * theta.cos() / (EQUAL_EARTH_M * EqualEarth::dy(theta));
+    (x, EqualEarth::y(theta))
+  }
+
+  fn inverse(&self, x: f64, y: f64) -> Option<GeoPoint> {
+    // solve y(theta) = y with Newton's method
+    let mut theta = y / EQUAL_EARTH_A1;
+    for _ in 0..12 {
+      let delta = (EqualEarth::y(theta) - y) / EqualEarth::dy(theta);
+      theta -= delta;
+      if delta.abs() < 1e-9 {
+        break;
+      }
+    }
+    let sin_latitude = theta.sin() / EQUAL_EARTH_M;
+    if sin_latitude.abs() > 1.0 {
+      return None;
+    }
+    let longitude = EQUAL_EARTH_M * x * EqualEarth::dy(theta) / theta.cos();
+    let point = GeoPoint::new(longitude.to_degrees(), sin_latitude.asin().to_degrees());
+    Some(point).filter(|point| is_on_globe(*point))
+  }
+}
@@ -1,19 +1,48 @@
-use geojson::{Feature, FeatureCollection, GeoJson};
-use serde::Deserialize;
-use sfml::graphics::{Color, Rect};
-use std::{collections::HashMap, error::Error, fs::read_to_string};
+use geojson::Feature;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::{
+  color::Color,
   config::MapConfig,
+  errors::{FeatureRef, MapLoadError},
+  files::{read_features, read_json},
   geo_drawable::{Bounds, GeoDrawable},
+  label::LabelAnchor,
+  math::Vector2f,
+  nation::Nations,
+  projection::Projection,
+  world_map::LOAD_BOUNDS,
 };
 
 #[derive(Debug)]
 pub struct Province {
+  pub highlighted: bool,
+  pub selected: bool,
+  // the owner's color, which highlighting and selection tint
+  pub color: Color,
+  // the occupying nation's color, while another nation than the owner controls it
+  pub occupier_color: Option<Color>,
   pub geo_drawable: Box<GeoDrawable>,
 }
 pub type Provinces = HashMap<String, Box<Province>>;
 
+/* Identifies a province across the whole map, as province IDs are only unique within a nation */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProvinceKey {
+  pub nation_id: String,
+  pub province_id: String,
+}
+
+impl ProvinceKey {
+  pub fn new(nation_id: &str, province_id: &str) -> ProvinceKey {
+    ProvinceKey {
+      nation_id: nation_id.to_string(),
+      province_id: province_id.to_string(),
+    }
+  }
+}
+
 #[derive(Deserialize)]
 pub struct ProvinceMapping {
   pub name: Option<String>,
@@ -21,21 +50,33 @@ pub struct ProvinceMapping {
 }
 pub type ProvinceMappings = HashMap<String, ProvinceMapping>;
 
+impl ProvinceMapping {
+  pub fn name_property(mapping: Option<&ProvinceMapping>) -> &str {
+    mapping
+      .and_then(|m| m.name.as_deref())
+      .unwrap_or(DEFAULT_NAME_PROPERTY)
+  }
+
+  pub fn id_property(mapping: Option<&ProvinceMapping>) -> Option<&str> {
+    mapping.and_then(|m| m.id.as_deref())
+  }
+}
+
 const DEFAULT_NAME_PROPERTY: &str = "name";
 
 impl Province {
   pub fn load_mappings(
     config: &MapConfig,
-  ) -> Result<HashMap<String, ProvinceMapping>, Box<dyn Error>> {
-    let json_str = read_to_string(&config.province_mappings_path)?;
-    Ok(serde_json::from_str(&json_str)?)
+  ) -> Result<HashMap<String, ProvinceMapping>, MapLoadError> {
+    read_json(&config.province_mappings_path)
   }
 
   pub fn load_nation(
     config: &MapConfig,
+    projection: &dyn Projection,
     nation_id: String,
     mapping: Option<&ProvinceMapping>,
-  ) -> Result<Option<Provinces>, Box<dyn Error>> {
+  ) -> Result<Option<Provinces>, MapLoadError> {
     let path = config
       .provinces_dir
       .clone()
@@ -43,39 +84,177 @@ impl Province {
     if !path.exists() {
       return Ok(None);
     }
-    let geojson_str = read_to_string(path)?;
-    let geojson = geojson_str.parse::<GeoJson>()?;
-    let features = FeatureCollection::try_from(geojson)?;
-    let bounds = Rect::new(0.0, 0.0, 100.0, 100.0);
+    let features = read_features(&path)?;
     let mut provinces = HashMap::new();
-    for feature in features {
-      let province = Province::new(feature, &bounds, mapping)?;
+    for (index, feature) in features.into_iter().enumerate() {
+      let source = FeatureRef::new(&path, index);
+      let province = Province::new(feature, source, &LOAD_BOUNDS, projection, mapping)?;
       provinces.insert(province.geo_drawable.id.clone(), province);
     }
-    println!(
-      "Loaded {} provinces for nation {}",
-      provinces.len(),
-      nation_id
-    );
     Ok(Some(provinces))
   }
 
+  /*
+  Loads the map's global provinces file, if it has one, for nations without a provinces file of
+  their own. Each province goes to the nation named in its nation property or else the nation
+  containing its middle (its pole of inaccessibility, which unlike its centroid is always inside
+  it), picking the lowest ID where nations overlap; provinces which match no such nation are left
+  out. Returns the provinces by nation ID
+  */
+  pub fn load_global(
+    config: &MapConfig,
+    projection: &dyn Projection,
+    nations: &Nations,
+  ) -> Result<HashMap<String, Provinces>, MapLoadError> {
+    let path = &config.global_provinces_path;
+    let needs_provinces = |nation_id: &String| {
+      nations
+        .get(nation_id)
+        .is_some_and(|nation| nation.provinces.is_none())
+    };
+    if !path.exists() || !nations.keys().any(needs_provinces) {
+      return Ok(HashMap::new());
+    }
+    let mapping = ProvinceMapping {
+      name: Some(config.province_name_property.clone()),
+      id: config.province_id_property.clone(),
+    };
+    let mut by_nation: HashMap<String, Provinces> = HashMap::new();
+    for (index, feature) in read_features(path)?.into_iter().enumerate() {
+      let nation_id = config
+        .province_nation_property
+        .as_ref()
+        .and_then(|property| GeoDrawable::get_feature_property(&feature, property))
+        .filter(|nation_id| nations.contains_key(nation_id));
+      // checked before loading, which triangulates the province
+      if nation_id.as_ref().is_some_and(|id| !needs_provinces(id)) {
+        continue;
+      }
+      let source = FeatureRef::new(path, index);
+      let province = Province::new(feature, source, &LOAD_BOUNDS, projection, Some(&mapping))?;
+      let nation_id = nation_id.or_else(|| {
+        let middle = LabelAnchor::new(&province.geo_drawable.vector_polygons)?.position;
+        nations
+          .iter()
+          .filter(|(nation_id, nation)| needs_provinces(nation_id) && nation.includes(middle))
+          .map(|(nation_id, _)| nation_id)
+          .min()
+          .cloned()
+      });
+      let Some(nation_id) = nation_id else {
+        continue;
+      };
+      let provinces = by_nation.entry(nation_id.clone()).or_default();
+      let province_id = province.geo_drawable.id.clone();
+      if provinces.contains_key(&province_id) {
+        return Err(MapLoadError::InvalidProperty {
+          feature: FeatureRef::new(path, index).with_id(&province_id),
+          property: config
+            .province_id_property
+            .as_deref()
+            .unwrap_or("id")
+            .to_string(),
+          reason: format!(
+            "has the same ID as an earlier province of nation {}",
+            nation_id
+          ),
+        });
+      }
+      provinces.insert(province_id, province);
+    }
+    Ok(by_nation)
+  }
+
+  /* A single province covering a whole nation, for nations with no provinces of their own */
+  pub fn from_nation(nation: &GeoDrawable, projection: &dyn Projection) -> Box<Province> {
+    Province::from_parts(GeoDrawable::from_parts(
+      nation.id.clone(),
+      nation.name.clone(),
+      nation.geo_polygons.clone(),
+      nation.triangles.clone(),
+      nation.bounds.clone(),
+      &LOAD_BOUNDS,
+      projection,
+    ))
+  }
+
   pub fn new(
     feature: Feature,
+    source: FeatureRef,
     bounds: &Bounds,
+    projection: &dyn Projection,
     mapping: Option<&ProvinceMapping>,
-  ) -> Result<Box<Province>, Box<dyn Error>> {
-    let id_property = mapping.and_then(|m| m.id.as_ref().and_then(|i| Some(i.as_str())));
-    let name_property = mapping
-      .and_then(|m| m.name.clone())
-      .unwrap_or_else(|| DEFAULT_NAME_PROPERTY.to_string());
-    let geo_drawable = GeoDrawable::new(feature, bounds, name_property.as_str(), id_property)?;
-    let mut province = Box::new(Province { geo_drawable });
-    province.update_cached_vertices();
-    Ok(province)
-  }
-
-  pub fn update_cached_vertices(&mut self) {
-    self.geo_drawable.update_cached_vertices(Color::BLACK);
+  ) -> Result<Box<Province>, MapLoadError> {
+    let geo_drawable = GeoDrawable::new(
+      feature,
+      source,
+      bounds,
+      projection,
+      ProvinceMapping::name_property(mapping),
+      ProvinceMapping::id_property(mapping),
+    )?;
+    Ok(Province::from_parts(geo_drawable))
+  }
+
+  pub fn from_parts(geo_drawable: Box<GeoDrawable>) -> Box<Province> {
+    let mut province = Box::new(Province {
+      geo_drawable,
+      highlighted: false,
+      selected: false,
+      color: Color::LIGHT_GRAY,
+      occupier_color: None,
+    });
+    province.update_colors();
+    province
+  }
+
+  pub fn set_color(&mut self, color: Color) {
+    self.color = color;
+    self.update_colors();
+  }
+
+  pub fn set_occupier_color(&mut self, color: Option<Color>) {
+    self.occupier_color = color;
+    self.update_colors();
+  }
+
+  pub fn is_highlighted(&self) -> bool {
+    self.highlighted
+  }
+
+  pub fn is_selected(&self) -> bool {
+    self.selected
+  }
+
+  pub fn set_highlighted(&mut self, value: bool) {
+    self.highlighted = value;
+    self.update_colors();
+  }
+
+  pub fn set_selected(&mut self, value: bool) {
+    self.selected = value;
+    self.update_colors();
+  }
+
+  pub fn id(&self) -> &String {
+    &self.geo_drawable.id
+  }
+
+  pub fn includes(&self, position: Vector2f) -> bool {
+    self.geo_drawable.includes(position)
+  }
+
+  /* Unhighlighted provinces have no fill of their own, so their owner's fill shows through, unless
+  they're occupied */
+  pub fn update_colors(&mut self) {
+    let (fill_color, outline_color) = if self.is_selected() {
+      (Some(self.color.mix(Color::BLUE, 0.5)), Color::BLUE)
+    } else if self.is_highlighted() {
+      (Some(self.color.mix(Color::WHITE, 0.6)), Color::GREEN)
+    } else {
+      let occupied = self.occupier_color.map(|color| self.color.mix(color, 0.5));
+      (occupied, Color::BLACK)
+    };
+    self.geo_drawable.set_colors(fill_color, outline_color);
   }
 }
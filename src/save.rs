@@ -0,0 +1,204 @@
+use crate::{
+  calendar::GameDate,
+  config::{MapConfig, user_config_dir},
+  errors::MapLoadError,
+  ownership::{Ownership, ProvinceTransfer},
+  player::Player,
+  simulation::GameState,
+};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  fs,
+  io::{Read, Write},
+  path::{Path, PathBuf},
+};
+
+/*
+Bump whenever `SaveGame` (or anything it holds) changes in a way old saves can't be read as, and add
+a migration from the previous version to `MIGRATIONS`
+*/
+pub const SAVE_VERSION: u32 = 2;
+const MAGIC: &[u8; 4] = b"MGSV";
+pub const SAVE_EXTENSION: &str = "mgsave";
+pub const QUICK_SAVE_NAME: &str = "quicksave";
+// the newest is `autosave-1`
+pub const AUTOSAVE_SLOTS: usize = 3;
+
+/* Upgrades a saved game's JSON by one version: `MIGRATIONS[n]` from version n + 1 to n + 2 */
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+// one per version after the first, so a new version without a migration doesn't compile
+const MIGRATIONS: [Migration; SAVE_VERSION as usize - 1] = [add_territory];
+
+/*
+A game in progress, as written to a save file: the header (`MAGIC`, then the version as a
+little-endian u32), then the game as zlib-compressed JSON
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+  // the map folder's name
+  pub map: String,
+  // the map's `map_cache::map_hash` when saved, so a save isn't loaded onto a map which has changed
+  pub map_hash: u64,
+  pub state: GameState,
+  pub players: Vec<Player>,
+  // every province held by another nation than the one it was loaded from (added in version 2)
+  pub territory: Vec<ProvinceTransfer>,
+}
+
+impl SaveGame {
+  pub fn new(
+    config: &MapConfig,
+    map_hash: u64,
+    state: &GameState,
+    players: &[Player],
+    ownership: &Ownership,
+  ) -> SaveGame {
+    SaveGame {
+      map: config.name.clone(),
+      map_hash,
+      state: state.clone(),
+      players: players.to_vec(),
+      territory: ownership.transfers(),
+    }
+  }
+
+  /* Writes to a temporary file first, so a failed save leaves any old one as it was */
+  pub fn write(&self, path: &Path) -> Result<(), MapLoadError> {
+    let to_error = |error| MapLoadError::io(path, error);
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).map_err(to_error)?;
+    }
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, self.to_bytes(path)?).map_err(to_error)?;
+    fs::rename(&temporary_path, path).map_err(to_error)
+  }
+
+  pub fn read(path: &Path) -> Result<SaveGame, MapLoadError> {
+    let bytes = fs::read(path).map_err(|error| MapLoadError::io(path, error))?;
+    SaveGame::from_bytes(path, &bytes)
+  }
+
+  pub fn to_bytes(&self, path: &Path) -> Result<Vec<u8>, MapLoadError> {
+    let json = serde_json::to_vec(self).map_err(|error| save_error(path, error))?;
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(SAVE_VERSION.to_le_bytes());
+    let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+    encoder
+      .write_all(&json)
+      .and_then(|_| encoder.finish())
+      .map_err(|error| MapLoadError::io(path, error))
+  }
+
+  /* Reads a save written by this or an older version, migrating it to this one */
+  pub fn from_bytes(path: &Path, bytes: &[u8]) -> Result<SaveGame, MapLoadError> {
+    let header_length = MAGIC.len() + 4;
+    if bytes.len() < header_length || &bytes[..MAGIC.len()] != MAGIC {
+      return Err(save_error(path, "not a saved game"));
+    }
+    let version = u32::from_le_bytes(bytes[MAGIC.len()..header_length].try_into().unwrap());
+    if version > SAVE_VERSION {
+      return Err(save_error(
+        path,
+        format!(
+          "saved by a newer version of mapgame (save version {}, expected at most {})",
+          version, SAVE_VERSION
+        ),
+      ));
+    }
+    let mut json = Vec::new();
+    ZlibDecoder::new(&bytes[header_length..])
+      .read_to_end(&mut json)
+      .map_err(|error| save_error(path, error))?;
+    let mut value = serde_json::from_slice(&json).map_err(|error| save_error(path, error))?;
+    migrate(&mut value, version).map_err(|reason| save_error(path, reason))?;
+    serde_json::from_value(value).map_err(|error| save_error(path, error))
+  }
+
+  /* Whether the save can be loaded onto the given map, as it is now */
+  pub fn check_map(
+    &self,
+    path: &Path,
+    config: &MapConfig,
+    map_hash: u64,
+  ) -> Result<(), MapLoadError> {
+    if self.map != config.name {
+      return Err(save_error(
+        path,
+        format!("saved on map {:?}, not {:?}", self.map, config.name),
+      ));
+    }
+    if self.map_hash != map_hash {
+      return Err(save_error(
+        path,
+        format!("map {:?} has changed since the game was saved", self.map),
+      ));
+    }
+    Ok(())
+  }
+}
+
+/* Upgrades a saved game's JSON from `version` to `SAVE_VERSION`, one version at a time */
+pub fn migrate(value: &mut Value, version: u32) -> Result<(), String> {
+  if version == 0 || version > SAVE_VERSION {
+    return Err(format!("unknown save version {}", version));
+  }
+  for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+    migration(value).map_err(|reason| {
+      format!(
+        "failed to upgrade from save version {} to {}: {}",
+        index + 1,
+        index + 2,
+        reason
+      )
+    })?;
+  }
+  Ok(())
+}
+
+/* Version 1 predates territory changing hands, so every province was still with its first owner */
+fn add_territory(value: &mut Value) -> Result<(), String> {
+  let save = value.as_object_mut().ok_or("not an object")?;
+  save.insert("territory".to_string(), Value::Array(Vec::new()));
+  Ok(())
+}
+
+/* Where games are saved: a `saves` folder in the user's config directory */
+pub fn saves_dir() -> Option<PathBuf> {
+  user_config_dir().map(|dir| dir.join("saves"))
+}
+
+pub fn save_path(dir: &Path, name: &str) -> PathBuf {
+  dir.join(name).with_extension(SAVE_EXTENSION)
+}
+
+pub fn autosave_path(dir: &Path, slot: usize) -> PathBuf {
+  save_path(dir, &format!("autosave-{}", slot))
+}
+
+/* Autosaves are made at the start of each game month */
+pub fn is_autosave_due(before: &GameDate, after: &GameDate) -> bool {
+  (before.year, before.month) != (after.year, after.month)
+}
+
+/* Writes an autosave to the first slot, moving the older ones down and dropping the oldest */
+pub fn autosave(dir: &Path, save: &SaveGame) -> Result<PathBuf, MapLoadError> {
+  for slot in (1..AUTOSAVE_SLOTS).rev() {
+    let from = autosave_path(dir, slot);
+    if from.exists() {
+      let to = autosave_path(dir, slot + 1);
+      fs::rename(&from, &to).map_err(|error| MapLoadError::io(&from, error))?;
+    }
+  }
+  let path = autosave_path(dir, 1);
+  save.write(&path)?;
+  Ok(path)
+}
+
+fn save_error(path: &Path, reason: impl ToString) -> MapLoadError {
+  MapLoadError::Save {
+    path: path.to_path_buf(),
+    reason: reason.to_string(),
+  }
+}
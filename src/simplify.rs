@@ -0,0 +1,179 @@
+use crate::{
+  geo::{Point, PointKey, distance, open_ring, ring_points, segment_distance, to_key},
+  geo_drawable::GeoPolygons,
+};
+use std::{
+  cmp::Ordering,
+  collections::{HashMap, HashSet},
+};
+
+// in degrees, from finest to coarsest; level 0 is the unsimplified shape, level 1 the first of these
+pub const DETAIL_TOLERANCES: [f64; 4] = [0.02, 0.05, 0.1, 0.2];
+// a level is drawn once its borders stray from the originals by less than this many pixels
+const MAX_PIXEL_ERROR: f64 = 1.0;
+
+/* The coarsest level which looks the same as the full shapes at the given scale */
+pub fn detail_level(degrees_per_pixel: f64) -> usize {
+  DETAIL_TOLERANCES
+    .iter()
+    .take_while(|tolerance| **tolerance <= degrees_per_pixel * MAX_PIXEL_ERROR)
+    .count()
+}
+
+/*
+Simplifies every shape with Douglas-Peucker once per tolerance, keeping each point within the
+tolerance (in degrees) of the original border; the result is indexed by tolerance, then shape.
+Borders shared between shapes (point for point) are split off and simplified the same way from
+both sides, so neighbours stay aligned. Rings left with no area are dropped, along with a
+polygon's holes if its outer ring is.
+*/
+pub fn simplify_shapes(shapes: &[&GeoPolygons], tolerances: &[f64]) -> Vec<Vec<GeoPolygons>> {
+  let junctions = junctions(shapes);
+  let mut simplified = vec![Vec::with_capacity(shapes.len()); tolerances.len()];
+  for polygons in shapes {
+    let mut shape = vec![Vec::new(); tolerances.len()];
+    for polygon in polygons.iter() {
+      let mut rings = vec![Vec::new(); tolerances.len()];
+      for (index, ring) in polygon.iter().enumerate() {
+        let Some(chains) = Chains::new(ring, &junctions) else {
+          continue;
+        };
+        for (tolerance, rings) in tolerances.iter().zip(&mut rings) {
+          // a polygon whose outer ring is dropped loses its holes too
+          if index > 0 && rings.is_empty() {
+            continue;
+          }
+          rings.extend(chains.simplify(*tolerance));
+        }
+      }
+      for (rings, polygons) in rings.into_iter().zip(&mut shape) {
+        if !rings.is_empty() {
+          polygons.push(rings);
+        }
+      }
+    }
+    for (polygons, level) in shape.into_iter().zip(&mut simplified) {
+      level.push(polygons);
+    }
+  }
+  simplified
+}
+
+/*
+The points where the set of shapes sharing a border changes, as seen from any ring. Both sides of
+a border are split there, so they simplify the same way even if one side has points the other
+doesn't
+*/
+fn junctions(shapes: &[&GeoPolygons]) -> HashSet<PointKey> {
+  let mut owners: HashMap<PointKey, Vec<usize>> = HashMap::new();
+  for (index, polygons) in shapes.iter().enumerate() {
+    for point in polygons.iter().flatten().flatten() {
+      let shape_owners = owners.entry(to_key(point)).or_default();
+      if shape_owners.last() != Some(&index) {
+        shape_owners.push(index);
+      }
+    }
+  }
+  let mut junctions = HashSet::new();
+  for ring in shapes.iter().copied().flatten().flatten() {
+    let points = open_ring(ring);
+    let len = points.len();
+    let owners_at = |i: usize| owners.get(&to_key(&points[i % len]));
+    for (i, point) in points.iter().enumerate() {
+      if owners_at(i) != owners_at(i + len - 1) || owners_at(i) != owners_at(i + 1) {
+        junctions.insert(to_key(point));
+      }
+    }
+  }
+  junctions
+}
+
+/* A ring split at the points which must survive simplification */
+struct Chains {
+  points: Vec<Point>,
+  // indexes into `points`, ascending; each chain runs from one to the next, wrapping around
+  fixed: Vec<usize>,
+}
+
+impl Chains {
+  fn new(ring: &[Vec<f64>], junctions: &HashSet<PointKey>) -> Option<Chains> {
+    let points = ring_points(ring);
+    if points.len() < 3 {
+      return None;
+    }
+    let len = points.len();
+    let mut fixed: Vec<usize> = (0..len)
+      .filter(|i| junctions.contains(&to_key(&points[*i])))
+      .collect();
+    if fixed.len() < 2 {
+      // any other shape sharing the whole ring picks the same points: its junction or else its
+      // lowest point, and the point furthest from that
+      let start = fixed.first().copied().unwrap_or_else(|| {
+        (0..len)
+          .min_by(|a, b| {
+            points[*a]
+              .partial_cmp(&points[*b])
+              .unwrap_or(Ordering::Equal)
+          })
+          .unwrap_or(0)
+      });
+      let furthest = (0..len)
+        .max_by(|a, b| {
+          distance(points[*a], points[start]).total_cmp(&distance(points[*b], points[start]))
+        })
+        .unwrap_or(0);
+      fixed = vec![start.min(furthest), start.max(furthest)];
+      fixed.dedup();
+    }
+    Some(Chains { points, fixed })
+  }
+
+  /* The simplified ring, closed as GeoJSON expects, or None if it has no area left */
+  fn simplify(&self, tolerance: f64) -> Option<Vec<Vec<f64>>> {
+    let len = self.points.len();
+    let mut keep = vec![false; len];
+    for (n, start) in self.fixed.iter().enumerate() {
+      let end = self
+        .fixed
+        .get(n + 1)
+        .copied()
+        .unwrap_or(self.fixed[0] + len);
+      let chain: Vec<Point> = (*start..=end).map(|i| self.points[i % len]).collect();
+      for i in douglas_peucker(&chain, tolerance) {
+        keep[(start + i) % len] = true;
+      }
+    }
+    let mut simplified: Vec<Vec<f64>> = (0..len)
+      .filter(|i| keep[*i])
+      .map(|i| self.points[i].to_vec())
+      .collect();
+    if simplified.len() < 3 {
+      return None;
+    }
+    simplified.push(simplified[0].clone());
+    Some(simplified)
+  }
+}
+
+/* The indexes of the chain's points to keep, always including both ends */
+fn douglas_peucker(chain: &[Point], tolerance: f64) -> Vec<usize> {
+  let mut keep = vec![0, chain.len() - 1];
+  let mut stack = vec![(0, chain.len() - 1)];
+  while let Some((start, end)) = stack.pop() {
+    let (mut furthest, mut furthest_distance) = (start, 0.0);
+    for i in start + 1..end {
+      let distance = segment_distance(chain[i], chain[start], chain[end]);
+      if distance > furthest_distance {
+        (furthest, furthest_distance) = (i, distance);
+      }
+    }
+    if furthest_distance > tolerance {
+      keep.push(furthest);
+      stack.push((start, furthest));
+      stack.push((furthest, end));
+    }
+  }
+  keep.sort_unstable();
+  keep.dedup();
+  keep
+}
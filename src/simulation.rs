@@ -0,0 +1,131 @@
+use crate::calendar::GameDate;
+use serde::{Deserialize, Serialize};
+
+pub const START_DATE: GameDate = GameDate::new(2000, 1, 1, 0);
+// in game hours
+pub const TICK_HOURS: u32 = 1;
+// ticks per real second at speeds 1-5
+pub const SPEEDS: [f64; 5] = [2.0, 6.0, 24.0, 72.0, 240.0];
+// after a stall (e.g. the window being dragged), at most this many real seconds' worth of ticks are
+// caught up on, rather than running a burst of ticks which would stall the next frame too
+pub const MAX_CATCH_UP: f64 = 0.25;
+
+/* Everything the simulation advances, independent of rendering. Saved games hold it as it is, so
+changing it needs a migration in `save` */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+  pub date: GameDate,
+  // since the game started
+  pub ticks: u64,
+}
+
+impl GameState {
+  pub fn new() -> GameState {
+    GameState::starting_at(START_DATE)
+  }
+
+  pub fn starting_at(date: GameDate) -> GameState {
+    GameState { date, ticks: 0 }
+  }
+}
+
+impl Default for GameState {
+  fn default() -> GameState {
+    GameState::new()
+  }
+}
+
+/* Advances the game by one fixed step of `TICK_HOURS` */
+pub fn tick(state: &mut GameState) {
+  state.ticks += 1;
+  state.date.advance_hours(TICK_HOURS);
+}
+
+/*
+Decides how many ticks to run each frame, so the game advances at a steady rate set by the speed
+whatever the frame rate. Real time accumulates between frames and is spent in whole ticks; the
+remainder carries over to the next frame.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationClock {
+  paused: bool,
+  // 1-5, indexing `SPEEDS` from 1
+  speed: usize,
+  // real seconds not yet spent on ticks
+  accumulated: f64,
+}
+
+impl SimulationClock {
+  /* The game starts paused, at speed 1 */
+  pub fn new() -> SimulationClock {
+    SimulationClock {
+      paused: true,
+      speed: 1,
+      accumulated: 0.0,
+    }
+  }
+
+  /* Adds `delta` real seconds, returning how many ticks are now due */
+  pub fn update(&mut self, delta: f64) -> u32 {
+    if self.paused {
+      return 0;
+    }
+    let ticks_per_second = SPEEDS[self.speed - 1];
+    self.accumulated += delta;
+    let mut ticks = (self.accumulated * ticks_per_second).floor();
+    self.accumulated -= ticks / ticks_per_second;
+    let max_ticks = (MAX_CATCH_UP * ticks_per_second).ceil();
+    if ticks > max_ticks {
+      // the rest of the backlog is dropped
+      ticks = max_ticks;
+      self.accumulated = 0.0;
+    }
+    ticks as u32
+  }
+
+  /* Runs the ticks due after `delta` real seconds, returning how many ran */
+  pub fn advance(&mut self, state: &mut GameState, delta: f64) -> u32 {
+    let ticks = self.update(delta);
+    for _ in 0..ticks {
+      tick(state);
+    }
+    ticks
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused
+  }
+
+  pub fn set_paused(&mut self, paused: bool) {
+    self.paused = paused;
+    // time spent paused doesn't count towards the next tick
+    self.accumulated = 0.0;
+  }
+
+  pub fn toggle_paused(&mut self) {
+    self.set_paused(!self.paused);
+  }
+
+  pub fn speed(&self) -> usize {
+    self.speed
+  }
+
+  /* Sets the speed, clamped to 1-5 */
+  pub fn set_speed(&mut self, speed: usize) {
+    self.speed = speed.clamp(1, SPEEDS.len());
+  }
+
+  pub fn faster(&mut self) {
+    self.set_speed(self.speed + 1);
+  }
+
+  pub fn slower(&mut self) {
+    self.set_speed(self.speed.saturating_sub(1));
+  }
+}
+
+impl Default for SimulationClock {
+  fn default() -> SimulationClock {
+    SimulationClock::new()
+  }
+}
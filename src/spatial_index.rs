@@ -0,0 +1,92 @@
+use crate::{geo_drawable::Bounds, math::Vector2f};
+
+// the grid is split into this many cells along each axis
+pub const GRID_CELLS: usize = 64;
+
+/*
+A uniform grid over the map, bucketing items by their bounding rectangle so that a point
+query only has to look at the items overlapping the point's cell.
+*/
+#[derive(Debug)]
+pub struct SpatialIndex<T> {
+  bounds: Bounds,
+  columns: usize,
+  rows: usize,
+  // indexes into `items`, per cell in row-major order
+  cells: Vec<Vec<usize>>,
+  items: Vec<(Bounds, T)>,
+}
+
+impl<T> SpatialIndex<T> {
+  pub fn new(bounds: Bounds, columns: usize, rows: usize) -> SpatialIndex<T> {
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+    SpatialIndex {
+      bounds,
+      columns,
+      rows,
+      cells: vec![Vec::new(); columns * rows],
+      items: Vec::new(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  pub fn clear(&mut self, bounds: Bounds) {
+    self.bounds = bounds;
+    self.items.clear();
+    for cell in self.cells.iter_mut() {
+      cell.clear();
+    }
+  }
+
+  pub fn insert(&mut self, item_bounds: Bounds, item: T) {
+    let index = self.items.len();
+    let (first_column, first_row) = self.to_cell(Vector2f::new(item_bounds.left, item_bounds.top));
+    let (last_column, last_row) = self.to_cell(Vector2f::new(
+      item_bounds.left + item_bounds.width,
+      item_bounds.top + item_bounds.height,
+    ));
+    for row in first_row..=last_row {
+      for column in first_column..=last_column {
+        self.cells[row * self.columns + column].push(index);
+      }
+    }
+    self.items.push((item_bounds, item));
+  }
+
+  /* The items whose bounds contain `point`; these still need an exact test against their shape */
+  pub fn query(&self, point: Vector2f) -> impl Iterator<Item = &T> {
+    let cell: &[usize] = if self.bounds.contains(point) {
+      let (column, row) = self.to_cell(point);
+      &self.cells[row * self.columns + column]
+    } else {
+      &[]
+    };
+    cell
+      .iter()
+      .map(|index| &self.items[*index])
+      .filter(move |(bounds, _)| bounds.contains(point))
+      .map(|(_, item)| item)
+  }
+
+  /* The cell containing `point`, clamped to the grid */
+  fn to_cell(&self, point: Vector2f) -> (usize, usize) {
+    let to_index = |offset: f32, size: f32, count: usize| {
+      if size <= 0.0 {
+        return 0;
+      }
+      ((offset / size * count as f32).max(0.0) as usize).min(count - 1)
+    };
+    (
+      to_index(point.x - self.bounds.left, self.bounds.width, self.columns),
+      to_index(point.y - self.bounds.top, self.bounds.height, self.rows),
+    )
+  }
+}
@@ -0,0 +1,727 @@
+use crate::math::Vector2f;
+
+/*
+ear clipping with hole bridging, ported from mapbox's earcut
+https://github.com/mapbox/earcut
+*/
+
+// polygons with more points than this (across all rings) are ear-checked via a z-order curve
+const HASH_THRESHOLD: usize = 80;
+
+/*
+Triangulates a polygon whose first ring is the outer boundary and whose other rings are holes.
+Returns a triangle list as indices into all of the rings' points, concatenated in order.
+*/
+pub fn triangulate(rings: &[&[Vector2f]]) -> Vec<usize> {
+  let mut earcut = Earcut {
+    nodes: Vec::new(),
+    triangles: Vec::new(),
+    min_x: 0.0,
+    min_y: 0.0,
+    inv_size: 0.0,
+  };
+  earcut.run(rings);
+  earcut.triangles
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+  // index of the point in the concatenated rings
+  i: usize,
+  x: f64,
+  y: f64,
+  prev: usize,
+  next: usize,
+  z: u32,
+  prev_z: Option<usize>,
+  next_z: Option<usize>,
+  steiner: bool,
+}
+
+struct Earcut {
+  nodes: Vec<Node>,
+  triangles: Vec<usize>,
+  min_x: f64,
+  min_y: f64,
+  // zero when hashing is disabled
+  inv_size: f64,
+}
+
+impl Earcut {
+  fn run(&mut self, rings: &[&[Vector2f]]) {
+    let Some((outer, holes)) = rings.split_first() else {
+      return;
+    };
+    let Some(mut outer_node) = self.linked_list(outer, 0, true) else {
+      return;
+    };
+    if self.next(outer_node) == self.prev(outer_node) {
+      return;
+    }
+    let mut start = outer.len();
+    let mut queue = Vec::new();
+    for hole in holes {
+      if let Some(list) = self.linked_list(hole, start, false) {
+        if self.next(list) == list {
+          self.nodes[list].steiner = true;
+        }
+        queue.push(self.leftmost(list));
+      }
+      start += hole.len();
+    }
+    queue.sort_by(|a, b| self.nodes[*a].x.total_cmp(&self.nodes[*b].x));
+    for hole in queue {
+      outer_node = self.eliminate_hole(hole, outer_node);
+    }
+    if start > HASH_THRESHOLD {
+      let mut min_x = f64::MAX;
+      let mut min_y = f64::MAX;
+      let mut max_x = f64::MIN;
+      let mut max_y = f64::MIN;
+      for point in outer.iter() {
+        min_x = min_x.min(point.x as f64);
+        min_y = min_y.min(point.y as f64);
+        max_x = max_x.max(point.x as f64);
+        max_y = max_y.max(point.y as f64);
+      }
+      let size = f64::max(max_x - min_x, max_y - min_y);
+      self.min_x = min_x;
+      self.min_y = min_y;
+      self.inv_size = if size != 0.0 { 32767.0 / size } else { 0.0 };
+    }
+    self.earcut_linked(Some(outer_node), 0);
+  }
+
+  fn prev(&self, node: usize) -> usize {
+    self.nodes[node].prev
+  }
+
+  fn next(&self, node: usize) -> usize {
+    self.nodes[node].next
+  }
+
+  /* Creates a circular doubly linked list from the ring in the requested winding order */
+  fn linked_list(&mut self, ring: &[Vector2f], start: usize, clockwise: bool) -> Option<usize> {
+    let mut last = None;
+    if clockwise == (signed_area(ring) > 0.0) {
+      for (offset, point) in ring.iter().enumerate() {
+        last = Some(self.insert_node(start + offset, point, last));
+      }
+    } else {
+      for (offset, point) in ring.iter().enumerate().rev() {
+        last = Some(self.insert_node(start + offset, point, last));
+      }
+    }
+    if let Some(node) = last {
+      let next = self.next(node);
+      if self.equals(node, next) {
+        self.remove_node(node);
+        return Some(next);
+      }
+    }
+    last
+  }
+
+  fn insert_node(&mut self, i: usize, point: &Vector2f, last: Option<usize>) -> usize {
+    let index = self.nodes.len();
+    self.nodes.push(Node {
+      i,
+      x: point.x as f64,
+      y: point.y as f64,
+      prev: index,
+      next: index,
+      z: 0,
+      prev_z: None,
+      next_z: None,
+      steiner: false,
+    });
+    if let Some(last) = last {
+      let last_next = self.next(last);
+      self.nodes[index].next = last_next;
+      self.nodes[index].prev = last;
+      self.nodes[last_next].prev = index;
+      self.nodes[last].next = index;
+    }
+    index
+  }
+
+  fn remove_node(&mut self, node: usize) {
+    let Node {
+      prev,
+      next,
+      prev_z,
+      next_z,
+      ..
+    } = self.nodes[node];
+    self.nodes[next].prev = prev;
+    self.nodes[prev].next = next;
+    if let Some(prev_z) = prev_z {
+      self.nodes[prev_z].next_z = next_z;
+    }
+    if let Some(next_z) = next_z {
+      self.nodes[next_z].prev_z = prev_z;
+    }
+  }
+
+  /* Eliminates colinear or duplicate points */
+  fn filter_points(&mut self, start: usize, end: Option<usize>) -> usize {
+    let mut end = end.unwrap_or(start);
+    let mut p = start;
+    loop {
+      let mut again = false;
+      let prev = self.prev(p);
+      let next = self.next(p);
+      if !self.nodes[p].steiner && (self.equals(p, next) || self.area(prev, p, next) == 0.0) {
+        self.remove_node(p);
+        p = prev;
+        end = prev;
+        if p == self.next(p) {
+          break;
+        }
+        again = true;
+      } else {
+        p = next;
+      }
+      if !again && p == end {
+        break;
+      }
+    }
+    end
+  }
+
+  fn earcut_linked(&mut self, ear: Option<usize>, pass: u8) {
+    let Some(mut ear) = ear else {
+      return;
+    };
+    if pass == 0 && self.inv_size != 0.0 {
+      self.index_curve(ear);
+    }
+    let mut stop = ear;
+    while self.prev(ear) != self.next(ear) {
+      let prev = self.prev(ear);
+      let next = self.next(ear);
+      let is_ear = if self.inv_size != 0.0 {
+        self.is_ear_hashed(ear)
+      } else {
+        self.is_ear(ear)
+      };
+      if is_ear {
+        self.push_triangle(prev, ear, next);
+        self.remove_node(ear);
+        ear = self.next(next);
+        stop = ear;
+        continue;
+      }
+      ear = next;
+      if ear == stop {
+        match pass {
+          0 => {
+            let filtered = self.filter_points(ear, None);
+            self.earcut_linked(Some(filtered), 1);
+          }
+          1 => {
+            let filtered = self.filter_points(ear, None);
+            let cured = self.cure_local_intersections(filtered);
+            self.earcut_linked(Some(cured), 2);
+          }
+          _ => self.split_earcut(ear),
+        }
+        break;
+      }
+    }
+  }
+
+  fn push_triangle(&mut self, a: usize, b: usize, c: usize) {
+    self.triangles.push(self.nodes[a].i);
+    self.triangles.push(self.nodes[b].i);
+    self.triangles.push(self.nodes[c].i);
+  }
+
+  fn is_ear(&self, ear: usize) -> bool {
+    let a = self.prev(ear);
+    let c = self.next(ear);
+    if self.area(a, ear, c) >= 0.0 {
+      // reflex, can't be an ear
+      return false;
+    }
+    let (na, nb, nc) = (&self.nodes[a], &self.nodes[ear], &self.nodes[c]);
+    let x0 = na.x.min(nb.x).min(nc.x);
+    let y0 = na.y.min(nb.y).min(nc.y);
+    let x1 = na.x.max(nb.x).max(nc.x);
+    let y1 = na.y.max(nb.y).max(nc.y);
+    let mut p = self.next(c);
+    while p != a {
+      let np = &self.nodes[p];
+      if np.x >= x0
+        && np.x <= x1
+        && np.y >= y0
+        && np.y <= y1
+        && point_in_triangle(na, nb, nc, np.x, np.y)
+        && self.area(np.prev, p, np.next) >= 0.0
+      {
+        return false;
+      }
+      p = np.next;
+    }
+    true
+  }
+
+  fn is_ear_hashed(&self, ear: usize) -> bool {
+    let a = self.prev(ear);
+    let c = self.next(ear);
+    if self.area(a, ear, c) >= 0.0 {
+      return false;
+    }
+    let (na, nb, nc) = (&self.nodes[a], &self.nodes[ear], &self.nodes[c]);
+    let x0 = na.x.min(nb.x).min(nc.x);
+    let y0 = na.y.min(nb.y).min(nc.y);
+    let x1 = na.x.max(nb.x).max(nc.x);
+    let y1 = na.y.max(nb.y).max(nc.y);
+    let min_z = self.z_order(x0, y0);
+    let max_z = self.z_order(x1, y1);
+    let candidate = |p: usize| {
+      let np = &self.nodes[p];
+      np.x >= x0
+        && np.x <= x1
+        && np.y >= y0
+        && np.y <= y1
+        && p != a
+        && p != c
+        && point_in_triangle(na, nb, nc, np.x, np.y)
+        && self.area(np.prev, p, np.next) >= 0.0
+    };
+    // look for points inside the triangle in both directions along the z-order curve
+    let mut p = self.nodes[ear].prev_z;
+    let mut n = self.nodes[ear].next_z;
+    while let (Some(pp), Some(nn)) = (p, n) {
+      if self.nodes[pp].z < min_z || self.nodes[nn].z > max_z {
+        break;
+      }
+      if candidate(pp) || candidate(nn) {
+        return false;
+      }
+      p = self.nodes[pp].prev_z;
+      n = self.nodes[nn].next_z;
+    }
+    while let Some(pp) = p {
+      if self.nodes[pp].z < min_z {
+        break;
+      }
+      if candidate(pp) {
+        return false;
+      }
+      p = self.nodes[pp].prev_z;
+    }
+    while let Some(nn) = n {
+      if self.nodes[nn].z > max_z {
+        break;
+      }
+      if candidate(nn) {
+        return false;
+      }
+      n = self.nodes[nn].next_z;
+    }
+    true
+  }
+
+  /* Goes through all polygon nodes and cures small local self-intersections */
+  fn cure_local_intersections(&mut self, start: usize) -> usize {
+    let mut start = start;
+    let mut p = start;
+    loop {
+      let a = self.prev(p);
+      let b = self.next(self.next(p));
+      if !self.equals(a, b)
+        && self.intersects(a, p, self.next(p), b)
+        && self.locally_inside(a, b)
+        && self.locally_inside(b, a)
+      {
+        self.push_triangle(a, p, b);
+        let next = self.next(p);
+        self.remove_node(p);
+        self.remove_node(next);
+        p = b;
+        start = b;
+      }
+      p = self.next(p);
+      if p == start {
+        break;
+      }
+    }
+    self.filter_points(p, None)
+  }
+
+  /* Tries splitting the polygon into two along a valid diagonal and triangulating each half */
+  fn split_earcut(&mut self, start: usize) {
+    let mut a = start;
+    loop {
+      let mut b = self.next(self.next(a));
+      while b != self.prev(a) {
+        if self.nodes[a].i != self.nodes[b].i && self.is_valid_diagonal(a, b) {
+          let c = self.split_polygon(a, b);
+          let a = self.filter_points(a, Some(self.next(a)));
+          let c = self.filter_points(c, Some(self.next(c)));
+          self.earcut_linked(Some(a), 0);
+          self.earcut_linked(Some(c), 0);
+          return;
+        }
+        b = self.next(b);
+      }
+      a = self.next(a);
+      if a == start {
+        break;
+      }
+    }
+  }
+
+  /* Connects a hole to the outer ring with a bridge, turning both into a single ring */
+  fn eliminate_hole(&mut self, hole: usize, outer_node: usize) -> usize {
+    let Some(bridge) = self.find_hole_bridge(hole, outer_node) else {
+      return outer_node;
+    };
+    let bridge_reverse = self.split_polygon(bridge, hole);
+    self.filter_points(bridge_reverse, Some(self.next(bridge_reverse)));
+    self.filter_points(bridge, Some(self.next(bridge)))
+  }
+
+  /* David Eberly's algorithm for finding a bridge between a hole and the outer polygon */
+  fn find_hole_bridge(&self, hole: usize, outer_node: usize) -> Option<usize> {
+    let hx = self.nodes[hole].x;
+    let hy = self.nodes[hole].y;
+    let mut qx = f64::NEG_INFINITY;
+    let mut m = None;
+    let mut p = outer_node;
+    loop {
+      let np = &self.nodes[p];
+      let nn = &self.nodes[np.next];
+      if hy <= np.y && hy >= nn.y && nn.y != np.y {
+        let x = np.x + (hy - np.y) * (nn.x - np.x) / (nn.y - np.y);
+        if x <= hx && x > qx {
+          qx = x;
+          let candidate = if np.x < nn.x { p } else { np.next };
+          if x == hx {
+            // the hole touches the outer segment
+            return Some(candidate);
+          }
+          m = Some(candidate);
+        }
+      }
+      p = np.next;
+      if p == outer_node {
+        break;
+      }
+    }
+    let mut m = m?;
+    // look for points inside the triangle of hole point, segment intersection and endpoint;
+    // if there are none, the endpoint is a valid connection, otherwise pick the smallest angle
+    let stop = m;
+    let mx = self.nodes[m].x;
+    let my = self.nodes[m].y;
+    let mut tan_min = f64::INFINITY;
+    p = m;
+    loop {
+      let np = &self.nodes[p];
+      let (ax, cx) = if hy < my { (hx, qx) } else { (qx, hx) };
+      if hx >= np.x
+        && np.x >= mx
+        && hx != np.x
+        && point_in_triangle_xy(ax, hy, mx, my, cx, hy, np.x, np.y)
+      {
+        let tan = (hy - np.y).abs() / (hx - np.x);
+        let nm = &self.nodes[m];
+        if self.locally_inside(p, hole)
+          && (tan < tan_min
+            || (tan == tan_min
+              && (np.x > nm.x || (np.x == nm.x && self.sector_contains_sector(m, p)))))
+        {
+          m = p;
+          tan_min = tan;
+        }
+      }
+      p = np.next;
+      if p == stop {
+        break;
+      }
+    }
+    Some(m)
+  }
+
+  fn sector_contains_sector(&self, m: usize, p: usize) -> bool {
+    self.area(self.prev(m), m, self.prev(p)) < 0.0 && self.area(self.next(p), m, self.next(m)) < 0.0
+  }
+
+  fn leftmost(&self, start: usize) -> usize {
+    let mut p = start;
+    let mut leftmost = start;
+    loop {
+      let np = &self.nodes[p];
+      let nl = &self.nodes[leftmost];
+      if np.x < nl.x || (np.x == nl.x && np.y < nl.y) {
+        leftmost = p;
+      }
+      p = np.next;
+      if p == start {
+        break;
+      }
+    }
+    leftmost
+  }
+
+  /* Interlinks polygon nodes in z-order */
+  fn index_curve(&mut self, start: usize) {
+    let mut p = start;
+    loop {
+      if self.nodes[p].z == 0 {
+        self.nodes[p].z = self.z_order(self.nodes[p].x, self.nodes[p].y);
+      }
+      self.nodes[p].prev_z = Some(self.prev(p));
+      self.nodes[p].next_z = Some(self.next(p));
+      p = self.next(p);
+      if p == start {
+        break;
+      }
+    }
+    let prev_z = self.nodes[p].prev_z.unwrap_or(p);
+    self.nodes[prev_z].next_z = None;
+    self.nodes[p].prev_z = None;
+    self.sort_linked(p);
+  }
+
+  /* Simon Tatham's linked list merge sort, over the z-order links */
+  fn sort_linked(&mut self, list: usize) {
+    let mut list = Some(list);
+    let mut in_size = 1;
+    loop {
+      let mut p = list;
+      list = None;
+      let mut tail: Option<usize> = None;
+      let mut num_merges = 0;
+      while p.is_some() {
+        num_merges += 1;
+        let mut q = p;
+        let mut p_size = 0;
+        for _ in 0..in_size {
+          p_size += 1;
+          q = q.and_then(|q| self.nodes[q].next_z);
+          if q.is_none() {
+            break;
+          }
+        }
+        let mut q_size = in_size;
+        while p_size > 0 || (q_size > 0 && q.is_some()) {
+          let take_p = match (p, q) {
+            (Some(p), Some(q)) => {
+              p_size != 0 && (q_size == 0 || self.nodes[p].z <= self.nodes[q].z)
+            }
+            (Some(_), None) => p_size != 0,
+            _ => false,
+          };
+          let e = if take_p {
+            p_size -= 1;
+            p
+          } else {
+            q_size -= 1;
+            q
+          };
+          let Some(e) = e else {
+            break;
+          };
+          if take_p {
+            p = self.nodes[e].next_z;
+          } else {
+            q = self.nodes[e].next_z;
+          }
+          match tail {
+            Some(tail) => self.nodes[tail].next_z = Some(e),
+            None => list = Some(e),
+          }
+          self.nodes[e].prev_z = tail;
+          tail = Some(e);
+        }
+        p = q;
+      }
+      if let Some(tail) = tail {
+        self.nodes[tail].next_z = None;
+      }
+      in_size *= 2;
+      if num_merges <= 1 {
+        break;
+      }
+    }
+  }
+
+  /* z-order of a point given the polygon's bounding box */
+  fn z_order(&self, x: f64, y: f64) -> u32 {
+    let mut x = ((x - self.min_x) * self.inv_size) as u32;
+    let mut y = ((y - self.min_y) * self.inv_size) as u32;
+    x = (x | (x << 8)) & 0x00FF00FF;
+    x = (x | (x << 4)) & 0x0F0F0F0F;
+    x = (x | (x << 2)) & 0x33333333;
+    x = (x | (x << 1)) & 0x55555555;
+    y = (y | (y << 8)) & 0x00FF00FF;
+    y = (y | (y << 4)) & 0x0F0F0F0F;
+    y = (y | (y << 2)) & 0x33333333;
+    y = (y | (y << 1)) & 0x55555555;
+    x | (y << 1)
+  }
+
+  fn is_valid_diagonal(&self, a: usize, b: usize) -> bool {
+    let (na, nb) = (&self.nodes[a], &self.nodes[b]);
+    self.nodes[na.next].i != nb.i
+      && self.nodes[na.prev].i != nb.i
+      && !self.intersects_polygon(a, b)
+      && ((self.locally_inside(a, b)
+        && self.locally_inside(b, a)
+        && self.middle_inside(a, b)
+        && (self.area(na.prev, a, nb.prev) != 0.0 || self.area(a, nb.prev, b) != 0.0))
+        || (self.equals(a, b)
+          && self.area(na.prev, a, na.next) > 0.0
+          && self.area(nb.prev, b, nb.next) > 0.0))
+  }
+
+  fn area(&self, p: usize, q: usize, r: usize) -> f64 {
+    let (p, q, r) = (&self.nodes[p], &self.nodes[q], &self.nodes[r]);
+    (q.y - p.y) * (r.x - q.x) - (q.x - p.x) * (r.y - q.y)
+  }
+
+  fn equals(&self, a: usize, b: usize) -> bool {
+    self.nodes[a].x == self.nodes[b].x && self.nodes[a].y == self.nodes[b].y
+  }
+
+  fn intersects(&self, p1: usize, q1: usize, p2: usize, q2: usize) -> bool {
+    let o1 = sign(self.area(p1, q1, p2));
+    let o2 = sign(self.area(p1, q1, q2));
+    let o3 = sign(self.area(p2, q2, p1));
+    let o4 = sign(self.area(p2, q2, q1));
+    (o1 != o2 && o3 != o4)
+      || (o1 == 0 && self.on_segment(p1, p2, q1))
+      || (o2 == 0 && self.on_segment(p1, q2, q1))
+      || (o3 == 0 && self.on_segment(p2, p1, q2))
+      || (o4 == 0 && self.on_segment(p2, q1, q2))
+  }
+
+  /* For collinear points p, q, r, checks if q lies on segment pr */
+  fn on_segment(&self, p: usize, q: usize, r: usize) -> bool {
+    let (p, q, r) = (&self.nodes[p], &self.nodes[q], &self.nodes[r]);
+    q.x <= p.x.max(r.x) && q.x >= p.x.min(r.x) && q.y <= p.y.max(r.y) && q.y >= p.y.min(r.y)
+  }
+
+  fn intersects_polygon(&self, a: usize, b: usize) -> bool {
+    let ai = self.nodes[a].i;
+    let bi = self.nodes[b].i;
+    let mut p = a;
+    loop {
+      let next = self.next(p);
+      let pi = self.nodes[p].i;
+      let ni = self.nodes[next].i;
+      if pi != ai && ni != ai && pi != bi && ni != bi && self.intersects(p, next, a, b) {
+        return true;
+      }
+      p = next;
+      if p == a {
+        return false;
+      }
+    }
+  }
+
+  fn locally_inside(&self, a: usize, b: usize) -> bool {
+    let (prev, next) = (self.prev(a), self.next(a));
+    if self.area(prev, a, next) < 0.0 {
+      self.area(a, b, next) >= 0.0 && self.area(a, prev, b) >= 0.0
+    } else {
+      self.area(a, b, prev) < 0.0 || self.area(a, next, b) < 0.0
+    }
+  }
+
+  fn middle_inside(&self, a: usize, b: usize) -> bool {
+    let px = (self.nodes[a].x + self.nodes[b].x) / 2.0;
+    let py = (self.nodes[a].y + self.nodes[b].y) / 2.0;
+    let mut inside = false;
+    let mut p = a;
+    loop {
+      let np = &self.nodes[p];
+      let nn = &self.nodes[np.next];
+      if (np.y > py) != (nn.y > py)
+        && nn.y != np.y
+        && px < (nn.x - np.x) * (py - np.y) / (nn.y - np.y) + np.x
+      {
+        inside = !inside;
+      }
+      p = np.next;
+      if p == a {
+        return inside;
+      }
+    }
+  }
+
+  /*
+  Links a and b with a bridge; if a and b are in the same ring it is split in two,
+  if they are in different rings they are merged into one. Returns the copy of b.
+  */
+  fn split_polygon(&mut self, a: usize, b: usize) -> usize {
+    let a2 = self.nodes.len();
+    let b2 = a2 + 1;
+    let an = self.next(a);
+    let bp = self.prev(b);
+    let mut node_a2 = self.nodes[a];
+    let mut node_b2 = self.nodes[b];
+    node_a2.prev_z = None;
+    node_a2.next_z = None;
+    node_b2.prev_z = None;
+    node_b2.next_z = None;
+    node_a2.steiner = false;
+    node_b2.steiner = false;
+    self.nodes.push(node_a2);
+    self.nodes.push(node_b2);
+    self.nodes[a].next = b;
+    self.nodes[b].prev = a;
+    self.nodes[a2].next = an;
+    self.nodes[an].prev = a2;
+    self.nodes[b2].next = a2;
+    self.nodes[a2].prev = b2;
+    self.nodes[bp].next = b2;
+    self.nodes[b2].prev = bp;
+    b2
+  }
+}
+
+fn signed_area(ring: &[Vector2f]) -> f64 {
+  let mut sum = 0.0;
+  let mut j = ring.len().wrapping_sub(1);
+  for (i, point) in ring.iter().enumerate() {
+    let prev = ring[j];
+    sum += (prev.x as f64 - point.x as f64) * (point.y as f64 + prev.y as f64);
+    j = i;
+  }
+  sum
+}
+
+fn sign(value: f64) -> i8 {
+  if value > 0.0 {
+    1
+  } else if value < 0.0 {
+    -1
+  } else {
+    0
+  }
+}
+
+fn point_in_triangle(a: &Node, b: &Node, c: &Node, px: f64, py: f64) -> bool {
+  point_in_triangle_xy(a.x, a.y, b.x, b.y, c.x, c.y, px, py)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn point_in_triangle_xy(
+  ax: f64,
+  ay: f64,
+  bx: f64,
+  by: f64,
+  cx: f64,
+  cy: f64,
+  px: f64,
+  py: f64,
+) -> bool {
+  (cx - px) * (ay - py) >= (ax - px) * (cy - py)
+    && (ax - px) * (by - py) >= (bx - px) * (ay - py)
+    && (bx - px) * (cy - py) >= (cx - px) * (by - py)
+}
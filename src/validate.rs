@@ -0,0 +1,407 @@
+use crate::{
+  config::MapConfig,
+  errors::MapLoadError,
+  files,
+  geo_drawable::{GeoDrawable, GeoPolygons},
+  math::{Vector2f, polygon_area, rings_contain},
+  province::{ProvinceMapping, ProvinceMappings},
+  triangulate::triangulate,
+};
+use geojson::{Feature, FeatureCollection, Value};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt, fs,
+  path::{Path, PathBuf},
+};
+
+// how many of a province's largest triangles are checked against its nation
+const CONTAINMENT_SAMPLES: usize = 64;
+// a province with more of its sampled area than this outside its nation is reported
+const MAX_OUTSIDE_FRACTION: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  // the map loads, but probably not as intended
+  Warning,
+  // the map fails to load, or loads with shapes missing or broken
+  Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+  pub severity: Severity,
+  pub path: PathBuf,
+  // the feature's ID, or its index if it has none
+  pub feature: Option<String>,
+  pub message: String,
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{}: {}", severity, self.path.display())?;
+    if let Some(feature) = &self.feature {
+      write!(f, ": feature {}", feature)?;
+    }
+    write!(f, ": {}", self.message)
+  }
+}
+
+/* Checks a whole map folder, returning every problem found rather than stopping at the first */
+pub fn validate_map(config: &MapConfig) -> Vec<Problem> {
+  let mut validator = Validator::default();
+  validator.validate(config);
+  validator.problems
+}
+
+#[derive(Default)]
+struct Validator {
+  problems: Vec<Problem>,
+}
+
+impl Validator {
+  fn report(&mut self, severity: Severity, path: &Path, feature: Option<&str>, message: String) {
+    self.problems.push(Problem {
+      severity,
+      path: path.to_path_buf(),
+      feature: feature.map(str::to_string),
+      message,
+    });
+  }
+
+  fn report_error(&mut self, error: &MapLoadError) {
+    let feature = error.feature().map(|feature| match &feature.id {
+      Some(id) => id.clone(),
+      None => format!("#{}", feature.index),
+    });
+    self.report(
+      Severity::Error,
+      error.path(),
+      feature.as_deref(),
+      error.message(),
+    );
+  }
+
+  fn validate(&mut self, config: &MapConfig) {
+    let mappings = self.read_mappings(&config.province_mappings_path);
+    let Some(features) = self.read_features(&config.nations_path) else {
+      return;
+    };
+    let nations = self.check_features(
+      &config.nations_path,
+      features,
+      &config.nation_name_property,
+      Some(&config.nation_id_property),
+    );
+
+    let mut province_files = HashSet::new();
+    for (nation_id, nation_polygons) in &nations {
+      let path = config.provinces_dir.join(nation_id.clone() + ".geojson");
+      // without either, the nation is played as a single province
+      if !path.exists() && !config.global_provinces_path.exists() {
+        let message = "has no provinces file, so is a single province".to_string();
+        self.report(
+          Severity::Warning,
+          &config.nations_path,
+          Some(nation_id),
+          message,
+        );
+        continue;
+      }
+      if !path.exists() {
+        continue;
+      }
+      province_files.insert(path.clone());
+      let Some(features) = self.read_features(&path) else {
+        continue;
+      };
+      let mapping = mappings.get(nation_id);
+      let name_property = ProvinceMapping::name_property(mapping);
+      let id_property = ProvinceMapping::id_property(mapping);
+      let provinces = self.check_features(&path, features, name_property, id_property);
+      for (province_id, province_polygons) in &provinces {
+        if is_mostly_outside(province_polygons, nation_polygons) {
+          let message = format!("lies mostly outside of nation {}", nation_id);
+          self.report(Severity::Error, &path, Some(province_id), message);
+        }
+      }
+    }
+
+    let nation_ids: HashSet<&String> = nations.iter().map(|(id, _)| id).collect();
+    self.check_global_provinces(config, &nation_ids);
+    for nation_id in mappings.keys() {
+      if !nation_ids.contains(nation_id) {
+        let message = format!("has a mapping for unknown nation {}", nation_id);
+        self.report(
+          Severity::Warning,
+          &config.province_mappings_path,
+          None,
+          message,
+        );
+      }
+    }
+    if let Ok(entries) = fs::read_dir(&config.provinces_dir) {
+      let mut unused: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+          path
+            .extension()
+            .is_some_and(|extension| extension == "geojson")
+        })
+        .filter(|path| !province_files.contains(path))
+        .collect();
+      unused.sort();
+      for path in unused {
+        let message = "is not used, as there is no nation with this ID".to_string();
+        self.report(Severity::Warning, &path, None, message);
+      }
+    }
+  }
+
+  fn check_global_provinces(&mut self, config: &MapConfig, nation_ids: &HashSet<&String>) {
+    let path = &config.global_provinces_path;
+    if !path.exists() {
+      return;
+    }
+    let Some(features) = self.read_features(path) else {
+      return;
+    };
+    if let Some(nation_property) = &config.province_nation_property {
+      let id_property = config.province_id_property.as_deref();
+      for (index, feature) in features.features.iter().enumerate() {
+        let label = GeoDrawable::get_feature_id(feature, id_property)
+          .unwrap_or_else(|| format!("#{}", index));
+        let message = match GeoDrawable::get_feature_property(feature, nation_property) {
+          Some(nation_id) if nation_ids.contains(&nation_id) => continue,
+          Some(nation_id) => format!(
+            "has unknown nation {}, so goes to the nation containing it",
+            nation_id
+          ),
+          None => format!(
+            "is missing its nation (property {:?}), so goes to the nation containing it",
+            nation_property
+          ),
+        };
+        self.report(Severity::Warning, path, Some(&label), message);
+      }
+    }
+    self.check_features(
+      path,
+      features,
+      &config.province_name_property,
+      config.province_id_property.as_deref(),
+    );
+  }
+
+  fn read_mappings(&mut self, path: &Path) -> ProvinceMappings {
+    files::read_json(path).unwrap_or_else(|error| {
+      self.report_error(&error);
+      HashMap::new()
+    })
+  }
+
+  fn read_features(&mut self, path: &Path) -> Option<FeatureCollection> {
+    files::read_features(path)
+      .map_err(|error| self.report_error(&error))
+      .ok()
+  }
+
+  /* Checks each feature's properties and geometry, returning the polygons of those with an ID */
+  fn check_features(
+    &mut self,
+    path: &Path,
+    features: FeatureCollection,
+    name_property: &str,
+    id_property: Option<&str>,
+  ) -> Vec<(String, GeoPolygons)> {
+    let mut seen_ids = HashSet::new();
+    let mut valid = Vec::new();
+    for (index, feature) in features.into_iter().enumerate() {
+      let id = GeoDrawable::get_feature_id(&feature, id_property);
+      let label = id.clone().unwrap_or_else(|| format!("#{}", index));
+      let label = Some(label.as_str());
+      match &id {
+        None => {
+          let message = format!(
+            "is missing its ID (property {:?})",
+            id_property.unwrap_or("id")
+          );
+          self.report(Severity::Error, path, label, message);
+        }
+        Some(id) if !seen_ids.insert(id.clone()) => {
+          let message = "has the same ID as an earlier feature".to_string();
+          self.report(Severity::Error, path, label, message);
+        }
+        Some(_) => {}
+      }
+      if GeoDrawable::get_feature_property(&feature, name_property).is_none() {
+        let message = format!("is missing its name (property {:?})", name_property);
+        self.report(Severity::Error, path, label, message);
+      }
+      let polygons = self.check_geometry(path, label, &feature);
+      if let Some(id) = id {
+        valid.push((id, polygons));
+      }
+    }
+    valid
+  }
+
+  fn check_geometry(&mut self, path: &Path, label: Option<&str>, feature: &Feature) -> GeoPolygons {
+    let polygons = match feature.geometry.as_ref().map(|geometry| &geometry.value) {
+      Some(Value::Polygon(polygon)) => vec![polygon.clone()],
+      Some(Value::MultiPolygon(polygons)) => polygons.clone(),
+      Some(value) => {
+        let message = format!(
+          "has unsupported geometry type {}, only Polygon and MultiPolygon are drawn",
+          value.type_name()
+        );
+        self.report(Severity::Error, path, label, message);
+        return Vec::new();
+      }
+      None => {
+        self.report(Severity::Error, path, label, "has no geometry".to_string());
+        return Vec::new();
+      }
+    };
+    for ring in polygons.iter().flatten() {
+      if ring.len() < 4 {
+        let message = format!(
+          "has a ring with only {} positions, at least 4 are needed",
+          ring.len()
+        );
+        self.report(Severity::Error, path, label, message);
+        continue;
+      }
+      if ring.first() != ring.last() {
+        let message = "has an unclosed ring, its first and last positions differ".to_string();
+        self.report(Severity::Error, path, label, message);
+      }
+      if let Some(point) = find_self_intersection(ring) {
+        let message = format!(
+          "has a self-intersecting ring near {:.4}, {:.4}",
+          point[0], point[1]
+        );
+        self.report(Severity::Error, path, label, message);
+      }
+    }
+    polygons
+  }
+}
+
+/*
+Whether most of the province's area lies outside every nation polygon, judged by its largest
+triangles. Interior points are used because coastlines are often simplified differently in each
+file, leaving many of a province's border points just outside its nation.
+*/
+fn is_mostly_outside(province: &GeoPolygons, nation: &GeoPolygons) -> bool {
+  let nation: Vec<Vec<Vec<Vector2f>>> = nation.iter().map(|polygon| to_rings(polygon)).collect();
+  let mut triangles: Vec<(f32, Vector2f)> = Vec::new();
+  for polygon in province {
+    let rings = to_rings(polygon);
+    let ring_slices: Vec<&[Vector2f]> = rings.iter().map(|ring| ring.as_slice()).collect();
+    let points: Vec<Vector2f> = rings.iter().flatten().copied().collect();
+    for triangle in triangulate(&ring_slices).chunks(3) {
+      let corners = [
+        points[triangle[0]],
+        points[triangle[1]],
+        points[triangle[2]],
+      ];
+      let centroid = (corners[0] + corners[1] + corners[2]) / 3.0;
+      triangles.push((polygon_area(&corners), centroid));
+    }
+  }
+  if triangles.is_empty() || nation.is_empty() {
+    return false;
+  }
+  triangles.sort_by(|a, b| b.0.total_cmp(&a.0));
+  triangles.truncate(CONTAINMENT_SAMPLES);
+  let total_area: f32 = triangles.iter().map(|(area, _)| area).sum();
+  let outside_area: f32 = triangles
+    .iter()
+    .filter(|(_, centroid)| !nation.iter().any(|rings| rings_contain(*centroid, rings)))
+    .map(|(area, _)| area)
+    .sum();
+  outside_area > total_area * MAX_OUTSIDE_FRACTION
+}
+
+/* A polygon's rings without GeoJSON's repeated last point */
+fn to_rings(polygon: &[Vec<Vec<f64>>]) -> Vec<Vec<Vector2f>> {
+  polygon
+    .iter()
+    .map(|ring| {
+      let points = match ring.split_last() {
+        Some((last, rest)) if rest.first() == Some(last) => rest,
+        _ => ring.as_slice(),
+      };
+      points.iter().map(|point| to_vector(point)).collect()
+    })
+    .collect()
+}
+
+fn to_vector(point: &[f64]) -> Vector2f {
+  Vector2f::new(point[0] as f32, point[1] as f32)
+}
+
+/*
+The first crossing between two of a closed ring's edges which aren't neighbours, if any. Edges are
+swept from west to east, so only edges overlapping in longitude are compared.
+*/
+fn find_self_intersection(ring: &[Vec<f64>]) -> Option<[f64; 2]> {
+  // repeated points don't make an edge, and mustn't stop the edges either side being neighbours
+  let edges: Vec<(usize, [f64; 2], [f64; 2])> = ring
+    .windows(2)
+    .map(|pair| ([pair[0][0], pair[0][1]], [pair[1][0], pair[1][1]]))
+    .filter(|(a, b)| a != b)
+    .enumerate()
+    .map(|(i, (a, b))| (i, a, b))
+    .collect();
+  let edge_count = edges.len();
+  let mut order: Vec<usize> = (0..edges.len()).collect();
+  let min_x = |edge: &(usize, [f64; 2], [f64; 2])| edge.1[0].min(edge.2[0]);
+  let max_x = |edge: &(usize, [f64; 2], [f64; 2])| edge.1[0].max(edge.2[0]);
+  order.sort_by(|a, b| min_x(&edges[*a]).total_cmp(&min_x(&edges[*b])));
+  let mut active: Vec<usize> = Vec::new();
+  for current in order {
+    let edge = &edges[current];
+    active.retain(|other| max_x(&edges[*other]) >= min_x(edge));
+    for other in &active {
+      let other = &edges[*other];
+      let (i, j) = (edge.0.min(other.0), edge.0.max(other.0));
+      // neighbouring edges share a point, including the last and first edges
+      if j - i == 1 || (i == 0 && j == edge_count - 1) {
+        continue;
+      }
+      if segments_intersect(edge.1, edge.2, other.1, other.2) {
+        return Some(edge.1);
+      }
+    }
+    active.push(current);
+  }
+  None
+}
+
+fn segments_intersect(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> bool {
+  let cross = |o: [f64; 2], p: [f64; 2], q: [f64; 2]| {
+    (p[0] - o[0]) * (q[1] - o[1]) - (p[1] - o[1]) * (q[0] - o[0])
+  };
+  let on_segment = |o: [f64; 2], p: [f64; 2], q: [f64; 2]| {
+    q[0] >= o[0].min(p[0])
+      && q[0] <= o[0].max(p[0])
+      && q[1] >= o[1].min(p[1])
+      && q[1] <= o[1].max(p[1])
+  };
+  let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+  let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+  if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+    && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+  {
+    return true;
+  }
+  (d1 == 0.0 && on_segment(c, d, a))
+    || (d2 == 0.0 && on_segment(c, d, b))
+    || (d3 == 0.0 && on_segment(a, b, c))
+    || (d4 == 0.0 && on_segment(a, b, d))
+}
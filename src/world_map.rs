@@ -1,136 +1,709 @@
-use geojson::FeatureCollection;
-use sfml::graphics::Rect;
-use sfml::{
-  graphics::{RenderTarget, RenderWindow},
-  system::Vector2f,
-};
-use std::{collections::HashMap, error::Error, fs::read_to_string, ops::Deref};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use crate::{
+  adjacency::Adjacency,
+  color::Color,
   config::MapConfig,
-  errors::MapLoadError,
-  geo_drawable::Bounds,
-  nation::{Nation, Nations},
-  province::Province,
+  dissolve::dissolve,
+  errors::{FeatureRef, MapLoadError},
+  files::read_features,
+  geo_drawable::{Bounds, DetailLevel, GeoDrawable, GeoPolygons},
+  map_cache::{self, CacheMiss},
+  map_mesh::ShapeKey,
+  math::{Rect, Vector2f},
+  nation::{Nation, NationColors, Nations},
+  ownership::{Ownership, ProvinceControl, ProvinceTransfer},
+  palette::assign_colors,
+  projection::{GeoPoint, Projection, project, unproject},
+  province::{Province, ProvinceKey},
+  simplify::{DETAIL_TOLERANCES, detail_level, simplify_shapes},
+  spatial_index::{GRID_CELLS, SpatialIndex},
 };
 
 // simply too many nations. this will be removed
 pub const MIN_NATION_AREA: f32 = 0.25;
+// how quickly the zoom animation converges on its target, per second
+pub const ZOOM_SMOOTHING: f32 = 12.0;
+// shapes are projected into these bounds while loading, until the first `on_resize`
+pub const LOAD_BOUNDS: Bounds = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+/* Everything loaded from a map folder (or its cache) before the world map is assembled */
+pub struct MapData {
+  pub nations: Nations,
+  // colors given by the map data, by nation ID; other nations get palette colors
+  pub colors: NationColors,
+  pub adjacency: Adjacency,
+}
+
+/* What lies under a point on the map; the province is only set if the nation owns one there */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapHit<'a> {
+  pub nation_id: &'a String,
+  pub province: Option<&'a ProvinceKey>,
+}
 
 pub struct WorldMap {
   nations: Nations,
+  adjacency: Adjacency,
+  // who holds each province; nations with provinces are drawn as the provinces they own
+  ownership: Ownership,
+  projection: Box<dyn Projection>,
   highlighted_nation_id: Option<String>,
-  // 0,0 is the middle of the window, so this can be negative
-  _view_center: Vector2f,
+  highlighted_province: Option<ProvinceKey>,
+  selected_province: Option<ProvinceKey>,
+  // (nation ID, polygon index) and (province, polygon index) by polygon bounds
+  nation_index: SpatialIndex<(String, usize)>,
+  province_index: SpatialIndex<(ProvinceKey, usize)>,
+  // the projected extent of the whole map, as last passed to `on_resize`
+  map_bounds: Bounds,
+  // 0,0 is the middle of the map, so this can be negative
+  view_center: Vector2f,
   // minimum 1, as this is the scale factor by which to zoom the map.
-  pub zoom: f32,
+  zoom: f32,
+  // the zoom being animated towards, and the map point which stays fixed on screen meanwhile
+  target_zoom: f32,
+  zoom_anchor: Vector2f,
+  // nations and provinces highlighted, selected or unselected since `take_changed_shapes`
+  changed_shapes: HashSet<ShapeKey>,
+  // whether any nation's borders have moved since `take_changed_geometry`
+  changed_geometry: bool,
+  // why the map was loaded from its sources rather than its cache, if it was
+  cache_miss: Option<CacheMiss>,
 }
 
 impl WorldMap {
-  pub fn new<'a>(config: &MapConfig) -> Result<WorldMap, Box<dyn Error>> {
-    let nations = WorldMap::load_nations(config)?;
+  pub fn new(config: &MapConfig) -> Result<WorldMap, MapLoadError> {
+    let projection = config.projection.projection();
+    let (map_data, cache_miss) = match map_cache::load_if_fresh(config, projection.as_ref()) {
+      Ok(map_data) => (map_data, None),
+      Err(miss) => (
+        WorldMap::load_map_data(config, projection.as_ref())?,
+        Some(miss),
+      ),
+    };
+    let MapData {
+      mut nations,
+      colors,
+      adjacency,
+    } = map_data;
+    WorldMap::add_detail_levels(&mut nations, projection.as_ref());
+    WorldMap::color_nations(&mut nations, &colors, &adjacency);
     Ok(WorldMap {
+      ownership: Ownership::new(&nations),
       nations,
+      adjacency,
+      projection,
       highlighted_nation_id: None,
-      _view_center: Vector2f::new(0.0, 0.0),
+      highlighted_province: None,
+      selected_province: None,
+      nation_index: SpatialIndex::new(Rect::new(0.0, 0.0, 0.0, 0.0), GRID_CELLS, GRID_CELLS),
+      province_index: SpatialIndex::new(Rect::new(0.0, 0.0, 0.0, 0.0), GRID_CELLS, GRID_CELLS),
+      map_bounds: Rect::new(0.0, 0.0, 0.0, 0.0),
+      view_center: Vector2f::new(0.0, 0.0),
       zoom: 1.0,
+      target_zoom: 1.0,
+      zoom_anchor: Vector2f::new(0.0, 0.0),
+      changed_shapes: HashSet::new(),
+      changed_geometry: false,
+      cache_miss,
     })
   }
 
-  fn parse_features(geojson_str: String) -> Result<FeatureCollection, geojson::Error> {
-    let geojson = geojson_str.parse::<geojson::GeoJson>()?;
-    geojson::FeatureCollection::try_from(geojson)
+  /* Loads and processes the map folder's sources, without using the cache */
+  pub fn load_map_data(
+    config: &MapConfig,
+    projection: &dyn Projection,
+  ) -> Result<MapData, MapLoadError> {
+    let (nations, colors) = WorldMap::load_nations(config, projection)?;
+    let sea_links = Adjacency::load_sea_links(config)?;
+    let adjacency = Adjacency::build(&nations, &sea_links);
+    Ok(MapData {
+      nations,
+      colors,
+      adjacency,
+    })
   }
 
-  fn load_nations(config: &MapConfig) -> Result<Nations, Box<dyn Error>> {
-    if !config.nations_path.exists() {
-      return Err(Box::new(MapLoadError {
-        reason: format!(
-          "No nations JSON found at {:?}",
-          config.nations_path.to_str()
-        ),
-      }));
-    }
-    let geojson_str = read_to_string(&config.nations_path)?;
-    let features = WorldMap::parse_features(geojson_str)?;
+  /* Loads nations along with their colors from the map data, where given, and their provinces:
+  from their own file, else the global provinces file, else one province covering the nation */
+  fn load_nations(
+    config: &MapConfig,
+    projection: &dyn Projection,
+  ) -> Result<(Nations, NationColors), MapLoadError> {
+    let features = read_features(&config.nations_path)?;
     let province_mappings = Province::load_mappings(config)?;
-    let bounds = Rect::new(0.0, 0.0, 100.0, 100.0);
+    // the colors file takes precedence over colors in the GeoJSON
+    let mut colors = Nation::load_colors(config)?;
     let mut nations = HashMap::new();
-    for feature in features {
-      let nation = Nation::new(feature, &bounds, config, &province_mappings)?;
+    for (index, feature) in features.into_iter().enumerate() {
+      let source = FeatureRef::new(&config.nations_path, index);
+      let color = Nation::read_color(&feature, &source, config)?;
+      let nation = Nation::new(
+        feature,
+        source,
+        &LOAD_BOUNDS,
+        projection,
+        config,
+        &province_mappings,
+      )?;
       let nation_id = nation.id().clone();
       if nation.area() > MIN_NATION_AREA {
+        if let Some(color) = color {
+          colors.entry(nation_id.clone()).or_insert(color);
+        }
         nations.insert(nation_id, nation);
       }
     }
-    Ok(nations)
+    for (nation_id, provinces) in Province::load_global(config, projection, &nations)? {
+      nations.get_mut(&nation_id).unwrap().provinces = Some(provinces);
+    }
+    // so every nation can be played
+    for nation in nations.values_mut() {
+      if nation.provinces.is_none() {
+        let province = Province::from_nation(&nation.geo_drawable, projection);
+        nation.provinces = Some(HashMap::from([(province.id().clone(), province)]));
+      }
+    }
+    Ok((nations, colors))
+  }
+
+  /* Precomputes every nation's and province's simplified versions. All shapes are simplified
+  together, so borders shared by nations and provinces stay aligned at every level */
+  fn add_detail_levels(nations: &mut Nations, projection: &dyn Projection) {
+    // sorted, so each simplified shape goes back to the shape it came from
+    let mut sorted_nations: Vec<(&String, &mut Box<Nation>)> = nations.iter_mut().collect();
+    sorted_nations.sort_by_key(|(nation_id, _)| *nation_id);
+    let mut geo_drawables: Vec<&mut GeoDrawable> = Vec::new();
+    for (_, nation) in sorted_nations {
+      geo_drawables.push(&mut nation.geo_drawable);
+      let mut provinces: Vec<_> = nation.provinces.iter_mut().flatten().collect();
+      provinces.sort_by_key(|(province_id, _)| *province_id);
+      geo_drawables.extend(
+        provinces
+          .into_iter()
+          .map(|(_, province)| province.geo_drawable.as_mut()),
+      );
+    }
+    let shapes: Vec<&GeoPolygons> = geo_drawables
+      .iter()
+      .map(|geo_drawable| &geo_drawable.geo_polygons)
+      .collect();
+    let mut levels: Vec<_> = simplify_shapes(&shapes, &DETAIL_TOLERANCES)
+      .into_iter()
+      .map(|level| level.into_iter())
+      .collect();
+    for geo_drawable in geo_drawables {
+      geo_drawable.details = DETAIL_TOLERANCES
+        .iter()
+        .zip(levels.iter_mut())
+        .filter_map(|(tolerance, level)| {
+          let geo_polygons = level.next()?;
+          Some(DetailLevel::new(
+            *tolerance,
+            geo_polygons,
+            &LOAD_BOUNDS,
+            projection,
+          ))
+        })
+        .collect();
+    }
+  }
+
+  /* Gives each nation its color from the map data, or a palette color differing from its neighbours' */
+  fn color_nations(nations: &mut Nations, colors: &NationColors, adjacency: &Adjacency) {
+    let mut ids: Vec<String> = nations.keys().cloned().collect();
+    ids.sort();
+    let indexes: HashMap<&String, usize> = ids.iter().enumerate().map(|(i, id)| (id, i)).collect();
+    let adjacent: BTreeSet<(usize, usize)> = ids
+      .iter()
+      .enumerate()
+      .flat_map(|(i, id)| {
+        adjacency
+          .nations
+          .neighbours(id)
+          .filter_map(|neighbour| indexes.get(neighbour))
+          .map(move |j| (i.min(*j), i.max(*j)))
+      })
+      .collect();
+    let known_colors: Vec<Option<Color>> = ids.iter().map(|id| colors.get(id).copied()).collect();
+    let assigned = assign_colors(&known_colors, &adjacent);
+    for (id, color) in ids.iter().zip(assigned) {
+      if let Some(nation) = nations.get_mut(id) {
+        nation.set_color(color);
+      }
+    }
+  }
+
+  pub fn nations(&self) -> &Nations {
+    &self.nations
+  }
+
+  /* Across every nation, including the single provinces of nations which have none of their own */
+  pub fn province_count(&self) -> usize {
+    self
+      .nations
+      .values()
+      .map(|nation| {
+        nation
+          .provinces
+          .as_ref()
+          .map_or(0, |provinces| provinces.len())
+      })
+      .sum()
+  }
+
+  pub fn cache_miss(&self) -> Option<&CacheMiss> {
+    self.cache_miss.as_ref()
+  }
+
+  pub fn ownership(&self) -> &Ownership {
+    &self.ownership
+  }
+
+  /*
+  Hands a province to a new owner and/or controller, redrawing the borders of any nations it moves
+  between and working out who they now border. Returns false, changing nothing, if the province
+  or either nation isn't on the map
+  */
+  pub fn set_province_control(&mut self, key: &ProvinceKey, control: ProvinceControl) -> bool {
+    if !self.can_hold(&control) {
+      return false;
+    }
+    let new_owner = control.owner.clone();
+    let Some(old) = self.ownership.set(key, control) else {
+      return false;
+    };
+    self.update_province_colors(key);
+    if old.owner != new_owner {
+      self.update_territory(&old.owner);
+      self.update_territory(&new_owner);
+      self
+        .adjacency
+        .update_nations(&self.nations, &[&old.owner, &new_owner]);
+      self.rebuild_index();
+    }
+    true
+  }
+
+  /* Resets every province to its original owner, then applies the transfers (as from a saved
+  game), skipping any which can't be made */
+  pub fn set_ownership(&mut self, transfers: &[ProvinceTransfer]) {
+    let mut nation_ids = BTreeSet::new();
+    let mut keys = Vec::new();
+    for transfer in self
+      .ownership
+      .transfers()
+      .into_iter()
+      .chain(transfers.to_vec())
+    {
+      nation_ids.insert(transfer.province.nation_id.clone());
+      nation_ids.insert(transfer.control.owner.clone());
+      keys.push(transfer.province);
+    }
+    self.ownership = Ownership::new(&self.nations);
+    for transfer in transfers {
+      if self.can_hold(&transfer.control) {
+        self
+          .ownership
+          .set(&transfer.province, transfer.control.clone());
+      }
+    }
+    for key in &keys {
+      self.update_province_colors(key);
+    }
+    for nation_id in &nation_ids {
+      self.update_territory(nation_id);
+    }
+    let nation_ids: Vec<&String> = nation_ids.iter().collect();
+    self.adjacency.update_nations(&self.nations, &nation_ids);
+    self.rebuild_index();
+  }
+
+  fn can_hold(&self, control: &ProvinceControl) -> bool {
+    self.nations.contains_key(&control.owner) && self.nations.contains_key(&control.controller)
+  }
+
+  /* A province is drawn in its owner's color, mixed with its occupier's */
+  fn update_province_colors(&mut self, key: &ProvinceKey) {
+    let Some(control) = self.ownership.get(key) else {
+      return;
+    };
+    let color = |nation_id: &String| self.nations.get(nation_id).map(|nation| nation.color);
+    let owner_color = color(&control.owner).unwrap_or(Color::LIGHT_GRAY);
+    let occupier_color = color(&control.controller).filter(|_| control.is_occupied());
+    if let Some(province) = self.get_province_mut(key) {
+      province.set_color(owner_color);
+      province.set_occupier_color(occupier_color);
+    }
   }
 
-  pub fn render(&self, window: &mut RenderWindow) {
-    for nation in self.nations.values() {
-      window.draw(nation.deref());
+  /* Redraws a nation's borders around the provinces it owns, at every detail level. The provinces'
+  levels were simplified together, so their shared borders still cancel out */
+  fn update_territory(&mut self, nation_id: &String) {
+    if !self.nations.contains_key(nation_id) {
+      return;
     }
-    self.highlighted_nation_id.clone().and_then(|nation_id| {
-      self.nations.get(&nation_id).and_then(|nation| {
-        window.draw(nation.deref());
-        Some(())
+    let provinces: Vec<&Province> = self
+      .ownership
+      .owned_by(nation_id)
+      .into_iter()
+      .filter_map(|key| self.get_province(key))
+      .collect();
+    let shapes = |level: Option<usize>| -> Vec<&GeoPolygons> {
+      provinces
+        .iter()
+        .filter_map(|province| match level {
+          Some(level) => Some(&province.geo_drawable.details.get(level)?.geo_polygons),
+          None => Some(&province.geo_drawable.geo_polygons),
+        })
+        .collect()
+    };
+    let world_bounds = self.projection_bounds();
+    let projection = self.projection.as_ref();
+    let geo_polygons = dissolve(&shapes(None));
+    let details: Vec<DetailLevel> = DETAIL_TOLERANCES
+      .iter()
+      .enumerate()
+      .map(|(level, tolerance)| {
+        let geo_polygons = dissolve(&shapes(Some(level)));
+        DetailLevel::new(*tolerance, geo_polygons, &world_bounds, projection)
       })
-    });
+      .collect();
+    let nation = self.nations.get_mut(nation_id).unwrap();
+    nation
+      .geo_drawable
+      .set_polygons(geo_polygons, details, &world_bounds, projection);
+    self.changed_geometry = true;
+  }
+
+  /* The bounds shapes are currently projected into */
+  fn projection_bounds(&self) -> Bounds {
+    match self.map_bounds.width > 0.0 {
+      true => self.map_bounds,
+      false => LOAD_BOUNDS,
+    }
+  }
+
+  /* Whether any nation's borders have moved since the last call, so the map's mesh needs
+  rebuilding */
+  pub fn take_changed_geometry(&mut self) -> bool {
+    std::mem::take(&mut self.changed_geometry)
+  }
+
+  /* Which nations and provinces border each other, by land or by sea link */
+  pub fn adjacency(&self) -> &Adjacency {
+    &self.adjacency
   }
 
   pub fn on_resize(&mut self, bounds: &Bounds) {
     for (_id, nation) in self.nations.iter_mut() {
-      nation.on_resize(&bounds);
+      nation.on_resize(bounds, self.projection.as_ref());
     }
+    self.map_bounds = *bounds;
+    self.rebuild_index();
+    self.clamp_view_center();
+  }
+
+  /* Re-buckets every nation and province polygon, as their projected bounds have changed */
+  fn rebuild_index(&mut self) {
+    self.nation_index.clear(self.map_bounds);
+    self.province_index.clear(self.map_bounds);
+    for (nation_id, nation) in self.nations.iter() {
+      for (index, bounds) in nation.geo_drawable.bounds.iter().enumerate() {
+        self
+          .nation_index
+          .insert(*bounds, (nation_id.clone(), index));
+      }
+      for (province_id, province) in nation.provinces.iter().flatten() {
+        for (index, bounds) in province.geo_drawable.bounds.iter().enumerate() {
+          let item = (ProvinceKey::new(nation_id, province_id), index);
+          self.province_index.insert(*bounds, item);
+        }
+      }
+    }
+  }
+
+  /* The nation and province at `position`, if any */
+  pub fn hit_test(&self, position: Vector2f) -> Option<MapHit<'_>> {
+    let nation_id = self.nation_at(position)?;
+    let province = self
+      .province_index
+      .query(position)
+      .filter(|(key, _)| self.ownership.owner(key) == Some(nation_id))
+      .find(|(key, index)| {
+        self
+          .get_province(key)
+          .is_some_and(|province| province.geo_drawable.polygon_includes(*index, position))
+      })
+      .map(|(key, _)| key);
+    Some(MapHit {
+      nation_id,
+      province,
+    })
+  }
+
+  /* The ID of the nation at `position`, if any */
+  pub fn nation_at(&self, position: Vector2f) -> Option<&String> {
+    self
+      .nation_index
+      .query(position)
+      .find(|(nation_id, index)| {
+        self.nations[nation_id]
+          .geo_drawable
+          .polygon_includes(*index, position)
+      })
+      .map(|(nation_id, _)| nation_id)
+  }
+
+  /* The nations and provinces whose colors changed since the last call, so their vertices can be
+  recolored in place */
+  pub fn take_changed_shapes(&mut self) -> Vec<ShapeKey> {
+    self.changed_shapes.drain().collect()
+  }
+
+  pub fn projection(&self) -> &dyn Projection {
+    self.projection.as_ref()
+  }
+
+  /* The longitude/latitude at a position on the map, if the position is on the globe */
+  pub fn to_geo_point(&self, position: Vector2f) -> Option<GeoPoint> {
+    unproject(self.projection.as_ref(), position, &self.map_bounds)
+  }
+
+  pub fn to_map_position(&self, point: GeoPoint) -> Vector2f {
+    project(self.projection.as_ref(), point, &self.map_bounds)
+  }
+
+  pub fn view_center(&self) -> Vector2f {
+    self.view_center
+  }
+
+  pub fn zoom(&self) -> f32 {
+    self.zoom
+  }
+
+  /* Which of the shapes' detail levels to draw at the current zoom; 0 is full detail */
+  pub fn detail_level(&self) -> usize {
+    let pixels_per_turn = self.map_bounds.width as f64 * self.zoom as f64;
+    if pixels_per_turn <= 0.0 {
+      return 0;
+    }
+    detail_level(360.0 / pixels_per_turn)
+  }
+
+  pub fn target_zoom(&self) -> f32 {
+    self.target_zoom
+  }
+
+  /* Zooms immediately, keeping the middle of the view in place */
+  pub fn set_zoom(&mut self, zoom: f32) {
+    self.zoom = zoom;
+    self.target_zoom = zoom;
+    self.clamp_view_center();
+  }
+
+  /* Starts animating towards `zoom`, keeping `anchor` (in map coordinates) at the same place on screen */
+  pub fn zoom_towards(&mut self, zoom: f32, anchor: Vector2f) {
+    self.target_zoom = zoom;
+    self.zoom_anchor = anchor;
+  }
+
+  /* Advances the zoom animation by `delta` seconds, returning whether the view changed */
+  pub fn update_zoom(&mut self, delta: f32) -> bool {
+    if self.zoom == self.target_zoom {
+      return false;
+    }
+    let progress = 1.0 - (-ZOOM_SMOOTHING * delta).exp();
+    let mut zoom = self.zoom + (self.target_zoom - self.zoom) * progress;
+    if (self.target_zoom - zoom).abs() < self.target_zoom * 0.001 {
+      zoom = self.target_zoom;
+    }
+    self.zoom_at(zoom, self.zoom_anchor);
+    true
+  }
+
+  fn zoom_at(&mut self, zoom: f32, anchor: Vector2f) {
+    // the anchor's offset from the middle of the view shrinks or grows with the zoom
+    let center = self.map_bounds.center() + self.view_center;
+    let new_center = anchor - (anchor - center) * (self.zoom / zoom);
+    self.zoom = zoom;
+    self.view_center = new_center - self.map_bounds.center();
+    self.clamp_view_center();
+  }
+
+  /* The part of the projected map currently in view, derived from the view center and zoom */
+  pub fn view_bounds(&self) -> Bounds {
+    let size = self.map_bounds.size() / self.zoom;
+    let center = self.map_bounds.center() + self.view_center;
+    Rect::new(
+      center.x - size.x / 2.0,
+      center.y - size.y / 2.0,
+      size.x,
+      size.y,
+    )
+  }
+
+  /* Moves the view by `delta` in map coordinates, stopping at the edges of the map */
+  pub fn pan(&mut self, delta: Vector2f) {
+    self.view_center = self.view_center + delta;
+    self.clamp_view_center();
+  }
+
+  fn clamp_view_center(&mut self) {
+    let size = self.map_bounds.size();
+    let max_x = f32::max(0.0, (size.x - size.x / self.zoom) / 2.0);
+    let max_y = f32::max(0.0, (size.y - size.y / self.zoom) / 2.0);
+    self.view_center = Vector2f::new(
+      self.view_center.x.clamp(-max_x, max_x),
+      self.view_center.y.clamp(-max_y, max_y),
+    );
   }
 
   /* Highlights the nation at `position` and unhighlights all others, returning the highlighted nation ID (if any) */
   pub fn set_highlighted_nation_at(&mut self, position: Vector2f) -> &Option<String> {
-    for (id, nation) in self.nations.iter_mut() {
-      if nation.includes(position) {
-        nation.set_highlighted(true);
-        self.highlighted_nation_id = Some(id.clone());
-      } else if nation.is_highlighted() {
-        nation.set_highlighted(false);
+    let new_highlighted_id = self.nation_at(position).cloned();
+    if new_highlighted_id != self.highlighted_nation_id {
+      if let Some(old_nation) = self
+        .highlighted_nation_id
+        .take()
+        .and_then(|id| self.get_nation_mut(&id))
+      {
+        old_nation.set_highlighted(false);
       }
+      if let Some(new_nation) = new_highlighted_id
+        .as_ref()
+        .and_then(|id| self.get_nation_mut(id))
+      {
+        new_nation.set_highlighted(true);
+      }
+      self.highlighted_nation_id = new_highlighted_id;
     }
     &self.highlighted_nation_id
   }
 
-  pub fn get_highlighted_nation(&self) -> Option<&Box<Nation>> {
-    let highlighted_id = self.highlighted_nation_id.clone()?;
-    self.nations.get(highlighted_id.as_str())
+  pub fn get_highlighted_nation(&self) -> Option<&Nation> {
+    let highlighted_id = self.highlighted_nation_id.as_ref()?;
+    self
+      .nations
+      .get(highlighted_id)
+      .map(|nation| nation.as_ref())
   }
 
-  /* Selects the highlighted nation and unselects the old one (if any) */
+  /* Selects the new nation and unselects the old one (either may be none) */
   pub fn set_selected_nation(
     &mut self,
     old_selected_id_opt: Option<&String>,
-    new_selected_id: &String,
+    new_selected_id_opt: Option<&String>,
   ) {
-    old_selected_id_opt.and_then(|old_selected_id| {
-      self.get_nation_mut(old_selected_id).and_then(|old_nation| {
-        old_nation.set_selected(false);
-        Some(())
-      })
-    });
-    self.get_nation_mut(new_selected_id).and_then(|new_nation| {
+    if let Some(old_nation) = old_selected_id_opt.and_then(|id| self.get_nation_mut(id)) {
+      old_nation.set_selected(false);
+    }
+    if let Some(new_nation) = new_selected_id_opt.and_then(|id| self.get_nation_mut(id)) {
       new_nation.set_selected(true);
-      Some(())
-    });
+    }
   }
 
   pub fn set_selected_nation_at(&mut self, position: Vector2f) -> Option<String> {
-    for (id, nation) in self.nations.iter_mut() {
-      if nation.includes(position) {
-        nation.set_selected(true);
-        return Some(id.clone());
+    let id = self.nation_at(position)?.clone();
+    self.get_nation_mut(&id)?.set_selected(true);
+    Some(id)
+  }
+
+  /* Highlights the province at `position` and unhighlights the old one, returning the highlighted province (if any) */
+  pub fn set_highlighted_province_at(&mut self, position: Vector2f) -> &Option<ProvinceKey> {
+    let new_highlighted = self
+      .hit_test(position)
+      .and_then(|hit| hit.province.cloned());
+    if new_highlighted != self.highlighted_province {
+      if let Some(old_province) = self
+        .highlighted_province
+        .take()
+        .and_then(|key| self.get_province_mut(&key))
+      {
+        old_province.set_highlighted(false);
       }
+      if let Some(new_province) = new_highlighted
+        .as_ref()
+        .and_then(|key| self.get_province_mut(key))
+      {
+        new_province.set_highlighted(true);
+      }
+      self.highlighted_province = new_highlighted;
+    }
+    &self.highlighted_province
+  }
+
+  pub fn get_highlighted_province(&self) -> Option<&Province> {
+    self.get_province(self.highlighted_province.as_ref()?)
+  }
+
+  pub fn get_selected_province(&self) -> Option<&Province> {
+    self.get_province(self.selected_province.as_ref()?)
+  }
+
+  pub fn highlighted_province(&self) -> Option<&ProvinceKey> {
+    self.highlighted_province.as_ref()
+  }
+
+  pub fn selected_province(&self) -> Option<&ProvinceKey> {
+    self.selected_province.as_ref()
+  }
+
+  /*
+  Selects the province at `position` if it belongs to a selected nation, unselecting the old one.
+  Returns the newly selected province; clicking outside of any selected nation's provinces
+  leaves the selection as it was and returns None.
+  */
+  pub fn set_selected_province_at(&mut self, position: Vector2f) -> Option<ProvinceKey> {
+    let hit = self.hit_test(position)?;
+    if !self.nations[hit.nation_id].is_selected() {
+      return None;
     }
-    None
+    let key = hit.province?.clone();
+    self.set_selected_province(Some(&key));
+    Some(key)
+  }
+
+  /* Selects the given province (or none) and unselects the old one (if any) */
+  pub fn set_selected_province(&mut self, key: Option<&ProvinceKey>) {
+    if let Some(old_province) = self
+      .selected_province
+      .take()
+      .and_then(|old_key| self.get_province_mut(&old_key))
+    {
+      old_province.set_selected(false);
+    }
+    if let Some(new_province) = key.and_then(|key| self.get_province_mut(key)) {
+      new_province.set_selected(true);
+      self.selected_province = key.cloned();
+    }
+  }
+
+  pub fn get_province(&self, key: &ProvinceKey) -> Option<&Province> {
+    self
+      .nations
+      .get(&key.nation_id)?
+      .provinces
+      .as_ref()?
+      .get(&key.province_id)
+      .map(|province| province.as_ref())
+  }
+
+  /* Taking a shape mutably counts as changing its colors, see `take_changed_shapes` */
+  fn get_province_mut(&mut self, key: &ProvinceKey) -> Option<&mut Box<Province>> {
+    let province = self
+      .nations
+      .get_mut(&key.nation_id)?
+      .provinces
+      .as_mut()?
+      .get_mut(&key.province_id)?;
+    self.changed_shapes.insert(ShapeKey::Province(key.clone()));
+    Some(province)
   }
 
   fn get_nation_mut(&mut self, id: &String) -> Option<&mut Box<Nation>> {
-    self.nations.get_mut(id)
+    let nation = self.nations.get_mut(id)?;
+    self.changed_shapes.insert(ShapeKey::Nation(id.clone()));
+    Some(nation)
   }
 }
@@ -0,0 +1,140 @@
+mod common;
+
+use common::{RUS_PATH, chukotka};
+use geojson::{Feature, Geometry, Value};
+use mapgame::{
+  errors::FeatureRef,
+  geo_drawable::GeoDrawable,
+  math::{Rect, Vector2f},
+  projection::{Equirectangular, GeoPoint, project},
+};
+use std::path::Path;
+
+fn world_bounds() -> Rect {
+  Rect::new(0.0, 0.0, 3600.0, 1800.0)
+}
+
+fn with_longitudes(feature: &Feature, map: impl Fn(f64) -> f64) -> Feature {
+  let Some(Value::MultiPolygon(polygons)) = feature.geometry.as_ref().map(|g| g.value.clone())
+  else {
+    panic!("expected Chukotka to be a MultiPolygon");
+  };
+  let polygons = polygons
+    .into_iter()
+    .map(|polygon| {
+      polygon
+        .into_iter()
+        .map(|ring| ring.into_iter().map(|p| vec![map(p[0]), p[1]]).collect())
+        .collect()
+    })
+    .collect();
+  Feature {
+    geometry: Some(Geometry::new(Value::MultiPolygon(polygons))),
+    ..feature.clone()
+  }
+}
+
+fn load(feature: Feature) -> Box<GeoDrawable> {
+  GeoDrawable::new(
+    feature,
+    FeatureRef::new(Path::new(RUS_PATH), 0),
+    &world_bounds(),
+    &Equirectangular,
+    "shapeName",
+    Some("shapeID"),
+  )
+  .unwrap()
+}
+
+fn to_vector(longitude: f64, latitude: f64) -> Vector2f {
+  project(
+    &Equirectangular,
+    GeoPoint::new(longitude, latitude),
+    &world_bounds(),
+  )
+}
+
+fn assert_no_map_wide_polygons(drawable: &GeoDrawable) {
+  let bounds = world_bounds();
+  for polygon_bounds in &drawable.bounds {
+    assert!(
+      polygon_bounds.width < bounds.width / 2.0,
+      "polygon spans {} of {} pixels",
+      polygon_bounds.width,
+      bounds.width
+    );
+  }
+  for ring in drawable.vector_polygons.iter().flatten() {
+    for pair in ring.windows(2) {
+      assert!((pair[1].x - pair[0].x).abs() < bounds.width / 2.0);
+    }
+  }
+}
+
+fn assert_close(actual: f32, expected: f32) {
+  assert!(
+    (actual - expected).abs() <= expected * 1e-3,
+    "expected {} to be close to {}",
+    actual,
+    expected
+  );
+}
+
+#[test]
+fn source_data_split_at_antimeridian_is_unchanged() {
+  let drawable = load(chukotka());
+  assert_no_map_wide_polygons(&drawable);
+  assert!(drawable.includes(to_vector(175.0, 66.0)));
+  assert!(drawable.includes(to_vector(-174.0, 66.0)));
+  assert!(!drawable.includes(to_vector(0.0, 66.0)));
+}
+
+#[test]
+fn longitudes_past_180_are_wrapped() {
+  let expected = load(chukotka());
+  // some sources use 0..360 longitudes, putting the eastern tip at 180..191
+  let feature = with_longitudes(&chukotka(), |lon| if lon < 0.0 { lon + 360.0 } else { lon });
+  let drawable = load(feature);
+  assert_no_map_wide_polygons(&drawable);
+  assert_close(drawable.vector_total_area, expected.vector_total_area);
+  assert!(drawable.includes(to_vector(-174.0, 66.0)));
+  assert!(drawable.includes(to_vector(175.0, 66.0)));
+}
+
+#[test]
+fn rings_crossing_the_antimeridian_are_split() {
+  // moving the eastern tip 5 degrees west makes its rings jump from +180 to -180
+  let shifted = with_longitudes(&chukotka(), |lon| if lon < 0.0 { lon + 355.0 } else { lon });
+  let expected = load(shifted.clone());
+  let wrapped = with_longitudes(&shifted, |lon| if lon > 180.0 { lon - 360.0 } else { lon });
+  let drawable = load(wrapped);
+  assert_no_map_wide_polygons(&drawable);
+  assert_close(drawable.vector_total_area, expected.vector_total_area);
+  assert_eq!(drawable.bounds.len(), expected.bounds.len());
+  assert!(drawable.includes(to_vector(178.0, 66.0)));
+  assert!(drawable.includes(to_vector(-178.0, 66.0)));
+  assert!(!drawable.includes(to_vector(0.0, 66.0)));
+}
+
+#[test]
+fn rings_circling_a_pole_are_closed_along_it() {
+  let ring: Vec<Vec<f64>> = (0..=8)
+    .map(|i| vec![-180.0 + (i % 8) as f64 * 45.0, -70.0])
+    .collect();
+  let feature = Feature {
+    geometry: Some(Geometry::new(Value::Polygon(vec![ring]))),
+    properties: Some(
+      serde_json::json!({ "shapeName": "ring", "shapeID": "ring" })
+        .as_object()
+        .unwrap()
+        .clone(),
+    ),
+    ..Default::default()
+  };
+  let drawable = load(feature);
+  // the polygon legitimately spans the whole map, edge to edge
+  assert_eq!(drawable.bounds.len(), 1);
+  assert_close(drawable.bounds[0].width, world_bounds().width);
+  assert!(drawable.includes(to_vector(10.0, -80.0)));
+  assert!(!drawable.includes(to_vector(10.0, -60.0)));
+}
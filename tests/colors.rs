@@ -0,0 +1,39 @@
+mod common;
+
+use common::load_world_map;
+use mapgame::{color::Color, palette::PALETTE};
+
+#[test]
+fn neighbours_never_share_a_generated_color() {
+  let world_map = load_world_map();
+  let nations = world_map.nations();
+  for (a, b, _) in world_map.adjacency().nations.iter() {
+    let (color_a, color_b) = (nations[a].color, nations[b].color);
+    assert!(PALETTE.contains(&color_a));
+    assert_ne!(color_a, color_b, "{} and {} share a color", a, b);
+  }
+}
+
+#[test]
+fn hex_colors_parse() {
+  assert_eq!(Color::from_hex("#e7c496"), Some(Color::rgb(231, 196, 150)));
+  assert_eq!(Color::from_hex("E7C496"), Some(Color::rgb(231, 196, 150)));
+  assert_eq!(
+    Color::from_hex("#e7c49680"),
+    Some(Color::rgba(231, 196, 150, 128))
+  );
+  for malformed in [
+    "",
+    "#",
+    "#fff",
+    "#e7c4",
+    "#e7c4960",
+    "#e7c4968000",
+    "#e7c49g",
+    "##e7c496",
+    "#e7c4 96",
+    "#e7c49é",
+  ] {
+    assert_eq!(Color::from_hex(malformed), None, "{:?}", malformed);
+  }
+}
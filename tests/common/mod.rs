@@ -0,0 +1,79 @@
+// each test file uses its own share of these
+#![allow(dead_code)]
+
+use geojson::{Feature, FeatureCollection, GeoJson};
+use mapgame::{config::MapConfig, world_map::WorldMap};
+use serde_json::{Value, json};
+use std::{
+  env,
+  fs::{self, read_to_string},
+  ops::Deref,
+  path::{Path, PathBuf},
+};
+
+pub const MAP_DIR: &str = "maps/earth";
+pub const RUS_PATH: &str = "maps/earth/provinces/RUS.geojson";
+const CHUKOTKA_ID: &str = "28173009B71378385007039";
+
+pub fn earth_config() -> MapConfig {
+  MapConfig::load("earth", Path::new(MAP_DIR)).unwrap()
+}
+
+pub fn load_world_map() -> WorldMap {
+  WorldMap::new(&earth_config()).unwrap()
+}
+
+/* Chukotka is split at the antimeridian in the source data, with its eastern tip at -180..-169 */
+pub fn chukotka() -> Feature {
+  let geojson = read_to_string(RUS_PATH)
+    .unwrap()
+    .parse::<GeoJson>()
+    .unwrap();
+  FeatureCollection::try_from(geojson)
+    .unwrap()
+    .into_iter()
+    .find(|feature| feature.property("shapeID").and_then(|id| id.as_str()) == Some(CHUKOTKA_ID))
+    .unwrap()
+}
+
+/* An empty folder of its own for a test, removed when dropped so failing tests don't leave it */
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  pub fn new(name: &str) -> TempDir {
+    let dir = env::temp_dir().join(format!("mapgame-test-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+  }
+}
+
+impl Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+/* A square feature with the given properties, `size` degrees across from its south-west corner */
+pub fn square(x: f64, y: f64, size: f64, properties: Value) -> Value {
+  json!({
+    "type": "Feature",
+    "properties": properties,
+    "geometry": {
+      "type": "Polygon",
+      "coordinates": [[[x, y], [x + size, y], [x + size, y + size], [x, y + size], [x, y]]]
+    }
+  })
+}
+
+pub fn feature_collection(features: Vec<Value>) -> String {
+  json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{TempDir, feature_collection, square};
+use mapgame::config::{ConfigOverrides, get_available_maps, load_config};
+use serde_json::json;
+use std::fs;
+
+/* A maps folder with a good map and one whose manifest has an unknown key */
+fn write_maps(dir: &TempDir) {
+  let nations = vec![square(
+    0.0,
+    0.0,
+    40.0,
+    json!({ "id": "ALA", "name": "Aland" }),
+  )];
+  for name in ["good", "bad"] {
+    let map_dir = dir.join("maps").join(name);
+    fs::create_dir_all(&map_dir).unwrap();
+    fs::write(
+      map_dir.join("nations.geojson"),
+      feature_collection(nations.clone()),
+    )
+    .unwrap();
+    fs::write(map_dir.join("provinces.json"), "{}").unwrap();
+  }
+  let manifest = json!({ "projection": "mercator", "colour_property": "colour" });
+  fs::write(dir.join("maps/bad/map.json"), manifest.to_string()).unwrap();
+  fs::write(dir.join("config.json"), "{}").unwrap();
+}
+
+#[test]
+fn broken_maps_only_fail_when_selected() {
+  let dir = TempDir::new("config-maps");
+  write_maps(&dir);
+  let overrides = |map_name: &str| ConfigOverrides {
+    config_path: Some(dir.join("config.json")),
+    map_name: Some(map_name.to_string()),
+    maps_dir: Some(dir.join("maps").to_string_lossy().into_owned()),
+    ..ConfigOverrides::default()
+  };
+  assert_eq!(load_config(&overrides("good")).unwrap().map.name, "good");
+  assert!(load_config(&overrides("bad")).is_err());
+  assert!(load_config(&overrides("missing")).is_err());
+
+  let maps = get_available_maps(&dir.join("maps").to_string_lossy()).unwrap();
+  assert!(maps["good"].is_ok());
+  assert!(maps["bad"].is_err());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{TempDir, load_world_map};
+use mapgame::{
+  headless::{AiController, Controller, HeadlessOptions, Script, ScriptedController, simulate},
+  simulation::GameState,
+};
+use std::fs;
+
+fn scripted(json: &str) -> Box<dyn Controller> {
+  let script: Script = serde_json::from_str(json).unwrap();
+  let player = script.players.into_iter().next().unwrap();
+  Box::new(ScriptedController::new(player.actions))
+}
+
+#[test]
+fn scripted_and_ai_players_pick_nations() {
+  let world_map = load_world_map();
+  let mut controllers = vec![
+    scripted(r#"{"players": [{"actions": [{"tick": 0, "play_as": "RUS"}]}]}"#),
+    Box::new(AiController),
+    Box::new(AiController),
+  ];
+  let mut state = GameState::new();
+  let players = simulate(&mut state, &world_map, &mut controllers, 48);
+
+  assert_eq!(state.ticks, 48);
+  assert_eq!(players[0].nation_id.as_deref(), Some("RUS"));
+  // Russia has the most provinces, but the scripted player acts first
+  let ai_nations: Vec<_> = players[1..]
+    .iter()
+    .map(|player| player.nation_id.clone().unwrap())
+    .collect();
+  assert!(!ai_nations.contains(&"RUS".to_string()));
+  assert_ne!(ai_nations[0], ai_nations[1]);
+}
+
+#[test]
+fn taken_and_unknown_nations_are_ignored() {
+  let world_map = load_world_map();
+  let mut controllers = vec![
+    scripted(r#"{"players": [{"actions": [{"tick": 0, "play_as": "USA"}]}]}"#),
+    scripted(r#"{"players": [{"actions": [{"tick": 1, "play_as": "USA"}]}]}"#),
+    scripted(r#"{"players": [{"actions": [{"tick": 0, "play_as": "ATLANTIS"}]}]}"#),
+  ];
+  let mut state = GameState::new();
+  let players = simulate(&mut state, &world_map, &mut controllers, 2);
+
+  assert_eq!(players[0].nation_id.as_deref(), Some("USA"));
+  assert_eq!(players[1].nation_id, None);
+  assert_eq!(players[2].nation_id, None);
+}
+
+#[test]
+fn bad_scripts_are_not_map_errors() {
+  let dir = TempDir::new("bad-script");
+  let path = dir.join("script.json");
+  fs::write(&path, r#"{"players": 3}"#).unwrap();
+  let options = HeadlessOptions {
+    script: Some(path.clone()),
+    ..HeadlessOptions::default()
+  };
+  let message = Script::load(&options).unwrap_err().to_string();
+  assert!(message.starts_with(&path.display().to_string()));
+  assert!(!message.contains("failed to load map"));
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::{TempDir, earth_config, feature_collection, square};
+use mapgame::{
+  config::MapConfig,
+  map_cache::{CacheMiss, compile, load_if_fresh},
+  world_map::{MapData, WorldMap},
+};
+use serde_json::json;
+use std::fs;
+
+/* Every shape's geometry, sorted by ID, for comparing map data */
+fn shapes(map_data: &MapData) -> Vec<String> {
+  let mut shapes: Vec<String> = map_data
+    .nations
+    .values()
+    .flat_map(|nation| {
+      let provinces = nation
+        .provinces
+        .iter()
+        .flatten()
+        .map(|(_, p)| &p.geo_drawable);
+      std::iter::once(&nation.geo_drawable).chain(provinces)
+    })
+    .map(|geo_drawable| {
+      format!(
+        "{} {} {:?} {:?} {:?}",
+        geo_drawable.id,
+        geo_drawable.name,
+        geo_drawable.geo_polygons,
+        geo_drawable.triangles,
+        geo_drawable.bounds
+      )
+    })
+    .collect();
+  shapes.sort();
+  shapes
+}
+
+fn borders(map_data: &MapData) -> Vec<String> {
+  let nations = map_data.adjacency.nations.iter();
+  let provinces = map_data.adjacency.provinces.iter();
+  let mut borders: Vec<String> = nations
+    .map(|(a, b, border)| format!("{} {} {:?}", a, b, border))
+    .chain(provinces.map(|(a, b, border)| format!("{:?} {:?} {:?}", a, b, border)))
+    .collect();
+  borders.sort();
+  borders
+}
+
+#[test]
+fn caches_read_back_the_same() {
+  let dir = TempDir::new("cache-round-trip");
+  let mut config = earth_config();
+  config.cache_path = dir.join("map.cache").into_boxed_path();
+  compile(&config).unwrap();
+
+  let projection = config.projection.projection();
+  let loaded = WorldMap::load_map_data(&config, projection.as_ref()).unwrap();
+  let cached = load_if_fresh(&config, projection.as_ref()).unwrap();
+  assert_eq!(shapes(&cached), shapes(&loaded));
+  assert_eq!(borders(&cached), borders(&loaded));
+  assert_eq!(cached.colors, loaded.colors);
+}
+
+#[test]
+fn corrupted_caches_are_rebuilt_from_the_sources() {
+  let dir = TempDir::new("cache-corrupted");
+  let nations = vec![square(
+    0.0,
+    0.0,
+    40.0,
+    json!({ "id": "ALA", "name": "Aland" }),
+  )];
+  fs::write(dir.join("nations.geojson"), feature_collection(nations)).unwrap();
+  fs::write(dir.join("provinces.json"), "{}").unwrap();
+  let config = MapConfig::load("corrupted", &dir).unwrap();
+  let cache_path = compile(&config).unwrap();
+
+  // after the header (magic, version and hash) and nation count, the nation's ID and name, then
+  // its polygon, ring and point counts and 5 points, which are followed by the triangle count and
+  // the first triangle index
+  let first_index = 16 + 4 + 7 + 9 + 12 + 5 * 16 + 4;
+  let mut bytes = fs::read(&cache_path).unwrap();
+  assert_eq!(bytes[first_index - 4..first_index], 6u32.to_le_bytes());
+  bytes[first_index..first_index + 4].copy_from_slice(&4u32.to_le_bytes());
+  fs::write(&cache_path, bytes).unwrap();
+
+  let projection = config.projection.projection();
+  assert!(matches!(
+    load_if_fresh(&config, projection.as_ref()),
+    Err(CacheMiss::Unreadable(_))
+  ));
+  let world_map = WorldMap::new(&config).unwrap();
+  assert!(matches!(
+    world_map.cache_miss(),
+    Some(CacheMiss::Unreadable(_))
+  ));
+  assert_eq!(world_map.nations()["ALA"].geo_drawable.triangles.len(), 6);
+}
+
+#[test]
+fn stale_caches_say_how_to_rebuild_them() {
+  let dir = TempDir::new("cache-stale");
+  let nation = |size| square(0.0, 0.0, size, json!({ "id": "ALA", "name": "Aland" }));
+  fs::write(
+    dir.join("nations.geojson"),
+    feature_collection(vec![nation(40.0)]),
+  )
+  .unwrap();
+  fs::write(dir.join("provinces.json"), "{}").unwrap();
+  let config = MapConfig::load("stale", &dir).unwrap();
+  let projection = config.projection.projection();
+  assert!(matches!(
+    load_if_fresh(&config, projection.as_ref()),
+    Err(CacheMiss::Missing)
+  ));
+  compile(&config).unwrap();
+
+  fs::write(
+    dir.join("nations.geojson"),
+    feature_collection(vec![nation(30.0)]),
+  )
+  .unwrap();
+  let Err(miss) = load_if_fresh(&config, projection.as_ref()) else {
+    panic!("loaded a cache older than its sources");
+  };
+  assert!(matches!(miss, CacheMiss::Stale { .. }));
+  assert!(miss.to_string().contains("run `mapgame compile stale`"));
+}
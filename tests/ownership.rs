@@ -0,0 +1,174 @@
+mod common;
+
+use mapgame::{
+  dissolve::dissolve, geo_drawable::GeoPolygons, map_mesh::ShapeKey, math::Rect,
+  ownership::ProvinceControl, province::ProvinceKey, world_map::WorldMap,
+};
+
+fn load_world_map() -> WorldMap {
+  let mut world_map = common::load_world_map();
+  world_map.on_resize(&Rect::new(0.0, 0.0, 1920.0, 1080.0));
+  world_map
+}
+
+fn square(x: f64, y: f64) -> GeoPolygons {
+  let ring = [
+    [x, y],
+    [x + 1.0, y],
+    [x + 1.0, y + 1.0],
+    [x, y + 1.0],
+    [x, y],
+  ];
+  vec![vec![ring.iter().map(|point| point.to_vec()).collect()]]
+}
+
+fn area(nation_id: &str, world_map: &WorldMap) -> f32 {
+  world_map.nations()[nation_id].area()
+}
+
+#[test]
+fn shared_borders_dissolve() {
+  let (a, b) = (square(0.0, 0.0), square(1.0, 0.0));
+  let merged = dissolve(&[&a, &b]);
+  assert_eq!(merged.len(), 1);
+  assert_eq!(merged[0].len(), 1);
+  // the two squares' corners, closed
+  assert_eq!(merged[0][0].len(), 7);
+
+  let apart = square(5.0, 5.0);
+  assert_eq!(dissolve(&[&a, &apart]).len(), 2);
+
+  // a ring of squares around an empty middle
+  let ring: Vec<GeoPolygons> = (0..3)
+    .flat_map(|x| (0..3).map(move |y| (x, y)))
+    .filter(|(x, y)| (*x, *y) != (1, 1))
+    .map(|(x, y)| square(x as f64, y as f64))
+    .collect();
+  let merged = dissolve(&ring.iter().collect::<Vec<_>>());
+  assert_eq!(merged.len(), 1);
+  assert_eq!(merged[0].len(), 2);
+}
+
+#[test]
+fn provinces_change_hands() {
+  let mut world_map = load_world_map();
+  let key = ProvinceKey::new("CHN", "62");
+  let province = world_map.get_province(&key).unwrap();
+  let point = province.geo_drawable.label.unwrap().position;
+  let province_area = province.geo_drawable.vector_total_area;
+  let china = area("CHN", &world_map);
+  let hit = world_map.hit_test(point).unwrap();
+  assert_eq!((hit.nation_id.as_str(), hit.province), ("CHN", Some(&key)));
+  world_map.take_changed_geometry();
+
+  assert!(world_map.set_province_control(&key, ProvinceControl::new("RUS")));
+  assert!(world_map.take_changed_geometry());
+  let hit = world_map.hit_test(point).unwrap();
+  assert_eq!((hit.nation_id.as_str(), hit.province), ("RUS", Some(&key)));
+  assert_eq!(world_map.ownership().owned_by("RUS").first(), Some(&&key));
+  assert_eq!(world_map.ownership().transfers().len(), 1);
+  let (china_without, russia_with) = (area("CHN", &world_map), area("RUS", &world_map));
+
+  // both nations are now drawn from their provinces, rather than the nations file
+  world_map.set_ownership(&[]);
+  assert!(world_map.ownership().transfers().is_empty());
+  assert_eq!(world_map.hit_test(point).unwrap().nation_id, "CHN");
+  let (china_with, russia_without) = (area("CHN", &world_map), area("RUS", &world_map));
+  assert!((china_with - china_without - province_area).abs() < province_area * 0.01);
+  assert!((russia_with - russia_without - province_area).abs() < province_area * 0.01);
+  assert!((china_with - china).abs() < china * 0.05);
+}
+
+#[test]
+fn occupied_provinces_are_tinted() {
+  let mut world_map = load_world_map();
+  let key = ProvinceKey::new("CHN", "62");
+  let occupied = ProvinceControl {
+    owner: "CHN".to_string(),
+    controller: "RUS".to_string(),
+  };
+  assert!(world_map.set_province_control(&key, occupied));
+  assert!(!world_map.take_changed_geometry());
+  let province = world_map.get_province(&key).unwrap();
+  assert!(province.geo_drawable.fill_color.is_some());
+  assert_eq!(
+    world_map
+      .hit_test(province.geo_drawable.label.unwrap().position)
+      .unwrap()
+      .nation_id,
+    "CHN"
+  );
+}
+
+#[test]
+fn provinces_only_go_to_nations_on_the_map() {
+  let mut world_map = load_world_map();
+  let key = ProvinceKey::new("CHN", "62");
+  assert!(!world_map.set_province_control(&key, ProvinceControl::new("ATLANTIS")));
+  let unknown = ProvinceKey::new("CHN", "ATLANTIS");
+  assert!(!world_map.set_province_control(&unknown, ProvinceControl::new("RUS")));
+  assert!(world_map.ownership().transfers().is_empty());
+
+  // France's only province is its whole outline, which doesn't touch Gansu
+  let polygons = |nation_id: &str, world_map: &WorldMap| {
+    world_map.nations()[nation_id]
+      .geo_drawable
+      .geo_polygons
+      .len()
+  };
+  let france = polygons("FRA", &world_map);
+  let gansu = world_map
+    .get_province(&key)
+    .unwrap()
+    .geo_drawable
+    .geo_polygons
+    .len();
+  assert!(world_map.set_province_control(&key, ProvinceControl::new("FRA")));
+  assert_eq!(polygons("FRA", &world_map), france + gansu);
+}
+
+#[test]
+fn borders_follow_provinces() {
+  let mut world_map = load_world_map();
+  let borders = |a: &str, b: &str, world_map: &WorldMap| {
+    world_map
+      .adjacency()
+      .nations
+      .are_adjacent(&a.to_string(), &b.to_string())
+  };
+  assert!(!borders("FRA", "MNG", &world_map));
+  assert!(borders("FRA", "ESP", &world_map));
+
+  // Gansu borders Mongolia, so France now does too
+  let key = ProvinceKey::new("CHN", "62");
+  assert!(world_map.set_province_control(&key, ProvinceControl::new("FRA")));
+  assert!(borders("FRA", "MNG", &world_map));
+  assert!(borders("CHN", "MNG", &world_map));
+  assert!(borders("FRA", "CHN", &world_map));
+  assert!(borders("FRA", "ESP", &world_map));
+
+  world_map.set_ownership(&[]);
+  assert!(!borders("FRA", "MNG", &world_map));
+  assert!(!borders("FRA", "CHN", &world_map));
+  assert!(borders("CHN", "MNG", &world_map));
+}
+
+#[test]
+fn changed_shapes_are_listed_once() {
+  let mut world_map = load_world_map();
+  world_map.take_changed_shapes();
+  world_map.set_selected_province(Some(&ProvinceKey::new("CHN", "ATLANTIS")));
+  world_map.set_selected_nation(None, Some(&"ATLANTIS".to_string()));
+  assert!(world_map.take_changed_shapes().is_empty());
+
+  let (gansu, qinghai) = (ProvinceKey::new("CHN", "62"), ProvinceKey::new("CHN", "63"));
+  for key in [&gansu, &qinghai, &gansu, &qinghai] {
+    world_map.set_selected_province(Some(key));
+  }
+  let mut changed = world_map.take_changed_shapes();
+  changed.sort_by_key(|key| format!("{:?}", key));
+  assert_eq!(
+    changed,
+    [ShapeKey::Province(gansu), ShapeKey::Province(qinghai)]
+  );
+}
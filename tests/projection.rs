@@ -0,0 +1,79 @@
+use mapgame::{
+  math::Rect,
+  projection::{
+    EqualEarth, Equirectangular, GeoPoint, MAX_LATITUDE, MERCATOR_MAX_LATITUDE, Mercator,
+    Projection, Robinson, project, unproject,
+  },
+};
+
+fn projections() -> Vec<(Box<dyn Projection>, f64)> {
+  // each with the furthest latitude it draws
+  vec![
+    (Box::new(Equirectangular), MAX_LATITUDE),
+    (Box::new(Mercator), MERCATOR_MAX_LATITUDE),
+    (Box::new(Robinson), MAX_LATITUDE),
+    (Box::new(EqualEarth), MAX_LATITUDE),
+  ]
+}
+
+/* The equator, 60 degrees either side of it and the furthest latitudes, at several longitudes */
+fn points(max_latitude: f64) -> Vec<GeoPoint> {
+  let latitudes = [0.0, 60.0, -60.0, max_latitude, -max_latitude];
+  let longitudes = [0.0, 45.0, -120.0, 179.9];
+  latitudes
+    .iter()
+    .flat_map(|latitude| {
+      longitudes
+        .iter()
+        .map(|longitude| GeoPoint::new(*longitude, *latitude))
+    })
+    .collect()
+}
+
+fn assert_near(actual: GeoPoint, expected: GeoPoint, tolerance: f64, projection: &dyn Projection) {
+  assert!(
+    (actual.longitude - expected.longitude).abs() < tolerance
+      && (actual.latitude - expected.latitude).abs() < tolerance,
+    "{:?}: {:?} came back as {:?}",
+    projection,
+    expected,
+    actual
+  );
+}
+
+#[test]
+fn projections_round_trip() {
+  for (projection, max_latitude) in projections() {
+    for point in points(max_latitude) {
+      let (x, y) = projection.forward(point);
+      let inverse = projection.inverse(x, y).unwrap();
+      assert_near(inverse, point, 1e-6, projection.as_ref());
+    }
+  }
+}
+
+#[test]
+fn screen_positions_round_trip() {
+  let bounds = Rect::new(100.0, 50.0, 1920.0, 1080.0);
+  for (projection, max_latitude) in projections() {
+    for point in points(max_latitude) {
+      let position = project(projection.as_ref(), point, &bounds);
+      let inverse = unproject(projection.as_ref(), position, &bounds).unwrap();
+      // positions are f32 pixels, so only accurate to a fraction of a degree
+      assert_near(inverse, point, 0.05, projection.as_ref());
+    }
+  }
+}
+
+#[test]
+fn mercator_clamps_near_the_poles() {
+  let (x, y) = Mercator.forward(GeoPoint::new(30.0, 89.0));
+  let inverse = Mercator.inverse(x, y).unwrap();
+  assert_near(
+    inverse,
+    GeoPoint::new(30.0, MERCATOR_MAX_LATITUDE),
+    1e-6,
+    &Mercator,
+  );
+  assert!(Mercator.inverse(x, y * 1.1).is_none());
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::{TempDir, feature_collection, square};
+use mapgame::{
+  config::MapConfig,
+  errors::MapLoadError,
+  ownership::ProvinceControl,
+  province::ProvinceKey,
+  validate::{Severity, validate_map},
+  world_map::WorldMap,
+};
+use serde_json::{Value, json};
+use std::fs;
+
+/*
+A map of three nations side by side with a global provinces file: Aland's provinces are found by
+their nation property, Borduria's by lying inside it, and Carpathia has none
+*/
+fn write_map(name: &str) -> TempDir {
+  let nations = vec![
+    square(0.0, 0.0, 40.0, json!({ "id": "ALA", "name": "Aland" })),
+    square(40.0, 0.0, 40.0, json!({ "id": "BOR", "name": "Borduria" })),
+    square(80.0, 0.0, 40.0, json!({ "id": "CAR", "name": "Carpathia" })),
+  ];
+  let provinces = vec![
+    square(
+      0.0,
+      0.0,
+      20.0,
+      json!({ "code": "A-1", "name": "West", "iso_a3": "ALA" }),
+    ),
+    // outside Aland, but its nation property says otherwise
+    square(
+      -20.0,
+      0.0,
+      20.0,
+      json!({ "code": "A-2", "name": "Islands", "iso_a3": "ALA" }),
+    ),
+    square(45.0, 5.0, 10.0, json!({ "code": "B-1", "name": "Hills" })),
+    square(
+      60.0,
+      20.0,
+      10.0,
+      json!({ "code": "B-2", "name": "Lakes", "iso_a3": "XXX" }),
+    ),
+    // in no nation at all
+    square(0.0, 60.0, 10.0, json!({ "code": "X-1", "name": "Nowhere" })),
+  ];
+  write_global_map(name, nations, provinces)
+}
+
+fn write_global_map(name: &str, nations: Vec<Value>, provinces: Vec<Value>) -> TempDir {
+  let dir = TempDir::new(name);
+  let manifest = json!({
+    "projection": "equirectangular",
+    "province_nation_property": "iso_a3",
+    "province_id_property": "code",
+    "files": { "global_provinces": "admin1.geojson" }
+  });
+  fs::write(dir.join("map.json"), manifest.to_string()).unwrap();
+  fs::write(dir.join("nations.geojson"), feature_collection(nations)).unwrap();
+  fs::write(dir.join("admin1.geojson"), feature_collection(provinces)).unwrap();
+  fs::write(dir.join("provinces.json"), "{}").unwrap();
+  dir
+}
+
+fn province_ids(world_map: &WorldMap, nation_id: &str) -> Vec<String> {
+  let mut ids: Vec<String> = world_map.nations()[nation_id]
+    .provinces
+    .iter()
+    .flatten()
+    .map(|(id, _)| id.clone())
+    .collect();
+  ids.sort();
+  ids
+}
+
+#[test]
+fn global_provinces_go_to_their_nations() {
+  let dir = write_map("global-provinces");
+  let config = MapConfig::load("global", &dir).unwrap();
+  let world_map = WorldMap::new(&config).unwrap();
+  assert_eq!(province_ids(&world_map, "ALA"), ["A-1", "A-2"]);
+  assert_eq!(province_ids(&world_map, "BOR"), ["B-1", "B-2"]);
+  // a single province covering the whole nation, named after it
+  assert_eq!(province_ids(&world_map, "CAR"), ["CAR"]);
+  assert_eq!(world_map.province_count(), 5);
+  let carpathia = world_map
+    .get_province(&ProvinceKey::new("CAR", "CAR"))
+    .unwrap();
+  assert_eq!(carpathia.geo_drawable.name, "Carpathia");
+  assert_eq!(
+    carpathia.geo_drawable.vector_total_area,
+    world_map.nations()["CAR"].area()
+  );
+}
+
+#[test]
+fn overlapping_nations_pick_provinces_by_lowest_id() {
+  // Aland overlaps Borduria and Carpathia, which cover the same ground
+  let nations = vec![
+    square(0.0, 0.0, 40.0, json!({ "id": "ALA", "name": "Aland" })),
+    square(20.0, 0.0, 40.0, json!({ "id": "CAR", "name": "Carpathia" })),
+    square(20.0, 0.0, 40.0, json!({ "id": "BOR", "name": "Borduria" })),
+  ];
+  let provinces = vec![square(
+    25.0,
+    5.0,
+    10.0,
+    json!({ "code": "M-1", "name": "March" }),
+  )];
+  let dir = write_global_map("overlapping-nations", nations, provinces);
+  // so Aland already has its provinces, and the march goes to Borduria
+  let aland = vec![square(
+    0.0,
+    0.0,
+    40.0,
+    json!({ "id": "A-1", "name": "Aland" }),
+  )];
+  fs::create_dir_all(dir.join("provinces")).unwrap();
+  fs::write(
+    dir.join("provinces").join("ALA.geojson"),
+    feature_collection(aland),
+  )
+  .unwrap();
+  let config = MapConfig::load("global", &dir).unwrap();
+  let world_map = WorldMap::new(&config).unwrap();
+  assert_eq!(province_ids(&world_map, "ALA"), ["A-1"]);
+  assert_eq!(province_ids(&world_map, "BOR"), ["M-1"]);
+  assert_eq!(province_ids(&world_map, "CAR"), ["CAR"]);
+}
+
+#[test]
+fn duplicate_global_province_ids_are_errors() {
+  let nations = vec![square(
+    0.0,
+    0.0,
+    40.0,
+    json!({ "id": "ALA", "name": "Aland" }),
+  )];
+  let provinces = vec![
+    square(
+      0.0,
+      0.0,
+      10.0,
+      json!({ "code": "A-1", "name": "West", "iso_a3": "ALA" }),
+    ),
+    square(20.0, 0.0, 10.0, json!({ "code": "A-1", "name": "East" })),
+  ];
+  let dir = write_global_map("duplicate-provinces", nations, provinces);
+  let config = MapConfig::load("global", &dir).unwrap();
+  let Err(error) = WorldMap::new(&config) else {
+    panic!("loaded a map with duplicate province IDs");
+  };
+  assert!(matches!(
+    error,
+    MapLoadError::InvalidProperty { ref property, .. } if property == "code"
+  ));
+  assert_eq!(error.feature().unwrap().index, 1);
+}
+
+#[test]
+fn synthesized_provinces_change_hands() {
+  let dir = write_map("synthesized-provinces");
+  let config = MapConfig::load("global", &dir).unwrap();
+  let mut world_map = WorldMap::new(&config).unwrap();
+  let key = ProvinceKey::new("CAR", "CAR");
+  let car = "CAR".to_string();
+  assert_eq!(world_map.adjacency().nations.neighbours(&car).count(), 1);
+  assert!(world_map.set_province_control(&key, ProvinceControl::new("BOR")));
+  assert_eq!(world_map.ownership().owned_count("BOR"), 3);
+  assert_eq!(world_map.ownership().owned_count("CAR"), 0);
+  assert_eq!(world_map.adjacency().nations.neighbours(&car).count(), 0);
+}
+
+#[test]
+fn global_provinces_are_validated() {
+  let dir = write_map("global-validate");
+  let config = MapConfig::load("global", &dir).unwrap();
+  let problems = validate_map(&config);
+  let warned = |feature: &str| {
+    problems.iter().any(|problem| {
+      problem.severity == Severity::Warning && problem.feature.as_deref() == Some(feature)
+    })
+  };
+  assert!(warned("B-1"));
+  assert!(warned("B-2"));
+  assert!(!warned("A-1"));
+  // nations without a provinces file still get provinces, so aren't warned about
+  assert!(!warned("CAR"));
+  assert!(
+    problems
+      .iter()
+      .all(|problem| problem.severity == Severity::Warning)
+  );
+}
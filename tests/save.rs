@@ -0,0 +1,176 @@
+mod common;
+
+use common::{TempDir, earth_config, feature_collection, square};
+use flate2::{Compression, write::ZlibEncoder};
+use mapgame::{
+  config::MapConfig,
+  errors::MapLoadError,
+  map_cache::map_hash,
+  ownership::{Ownership, ProvinceControl, ProvinceTransfer},
+  player::Player,
+  province::ProvinceKey,
+  save::{
+    AUTOSAVE_SLOTS, SAVE_VERSION, SaveGame, autosave, autosave_path, is_autosave_due, migrate,
+  },
+  simulation::{GameState, tick},
+};
+use serde_json::json;
+use std::{fs, io::Write, path::Path};
+
+fn save_game(config: &MapConfig, ticks: u64) -> SaveGame {
+  let mut state = GameState::new();
+  for _ in 0..ticks {
+    tick(&mut state);
+  }
+  let mut player = *Player::new();
+  player.nation_id = Some("FRA".to_string());
+  let mut save = SaveGame::new(
+    config,
+    42,
+    &state,
+    &[player, *Player::new()],
+    &Ownership::default(),
+  );
+  save.territory.push(ProvinceTransfer {
+    province: ProvinceKey::new("CHN", "CN-XZ"),
+    control: ProvinceControl {
+      owner: "IND".to_string(),
+      controller: "CHN".to_string(),
+    },
+  });
+  save
+}
+
+#[test]
+fn saves_round_trip_compressed() {
+  let save = save_game(&earth_config(), 1000);
+  let path = Path::new("test.mgsave");
+  let bytes = save.to_bytes(path).unwrap();
+  assert_eq!(&bytes[..4], b"MGSV");
+  assert_eq!(bytes[4..8], SAVE_VERSION.to_le_bytes());
+  assert!(bytes.len() < serde_json::to_vec(&save).unwrap().len());
+  assert_eq!(SaveGame::from_bytes(path, &bytes).unwrap(), save);
+}
+
+#[test]
+fn unreadable_saves_are_errors() {
+  let save = save_game(&earth_config(), 0);
+  let path = Path::new("test.mgsave");
+  let is_save_error =
+    |result: Result<SaveGame, MapLoadError>| matches!(result, Err(MapLoadError::Save { .. }));
+  assert!(is_save_error(SaveGame::from_bytes(path, b"not a save")));
+  let error = SaveGame::from_bytes(path, b"not a save").unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "invalid saved game: test.mgsave: not a saved game"
+  );
+
+  let mut newer = save.to_bytes(path).unwrap();
+  newer[4..8].copy_from_slice(&(SAVE_VERSION + 1).to_le_bytes());
+  assert!(is_save_error(SaveGame::from_bytes(path, &newer)));
+
+  let mut truncated = save.to_bytes(path).unwrap();
+  truncated.truncate(truncated.len() / 2);
+  assert!(is_save_error(SaveGame::from_bytes(path, &truncated)));
+
+  let mut value = serde_json::to_value(&save).unwrap();
+  assert!(migrate(&mut value, 0).is_err());
+  migrate(&mut value, SAVE_VERSION).unwrap();
+  assert_eq!(serde_json::from_value::<SaveGame>(value).unwrap(), save);
+}
+
+#[test]
+fn older_saves_are_migrated() {
+  let save = save_game(&earth_config(), 10);
+  let mut value = serde_json::to_value(&save).unwrap();
+  value.as_object_mut().unwrap().remove("territory");
+  let mut bytes = b"MGSV".to_vec();
+  bytes.extend(1u32.to_le_bytes());
+  let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+  encoder
+    .write_all(&serde_json::to_vec(&value).unwrap())
+    .unwrap();
+
+  let path = Path::new("version-1.mgsave");
+  let migrated = SaveGame::from_bytes(path, &encoder.finish().unwrap()).unwrap();
+  assert!(migrated.territory.is_empty());
+  assert_eq!(migrated.state, save.state);
+  assert_eq!(migrated.players, save.players);
+}
+
+#[test]
+fn saves_only_load_onto_the_map_they_were_made_on() {
+  let config = earth_config();
+  let hash = map_hash(&config).unwrap();
+  let mut save = save_game(&config, 0);
+  let path = Path::new("test.mgsave");
+  save.map_hash = hash;
+  assert!(save.check_map(path, &config, hash).is_ok());
+  assert!(save.check_map(path, &config, hash ^ 1).is_err());
+  save.map = "mars".to_string();
+  assert!(save.check_map(path, &config, hash).is_err());
+}
+
+#[test]
+fn saves_from_earlier_releases_still_load() {
+  let dir = TempDir::new("save-version-2");
+  let nations = vec![square(
+    0.0,
+    0.0,
+    40.0,
+    json!({ "id": "ALA", "name": "Aland" }),
+  )];
+  fs::write(dir.join("nations.geojson"), feature_collection(nations)).unwrap();
+  fs::write(dir.join("provinces.json"), "{}").unwrap();
+  let config = MapConfig::load("aland", &dir).unwrap();
+
+  // the map's hash as recorded by version 2, before global provinces were added to the hash
+  let save = save_game(&config, 100);
+  let mut value = serde_json::to_value(&save).unwrap();
+  value["map_hash"] = json!(0xe0e8_8697_2e21_9b0a_u64);
+  let mut bytes = b"MGSV".to_vec();
+  bytes.extend(2u32.to_le_bytes());
+  let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+  encoder
+    .write_all(&serde_json::to_vec(&value).unwrap())
+    .unwrap();
+
+  let path = Path::new("version-2.mgsave");
+  let loaded = SaveGame::from_bytes(path, &encoder.finish().unwrap()).unwrap();
+  assert!(
+    loaded
+      .check_map(path, &config, map_hash(&config).unwrap())
+      .is_ok()
+  );
+  assert_eq!(loaded.players[0].nation_id.as_deref(), Some("FRA"));
+  assert_eq!(loaded.state.ticks, 100);
+}
+
+#[test]
+fn autosaves_rotate() {
+  let config = earth_config();
+  let dir = TempDir::new("autosaves");
+  for ticks in 0..AUTOSAVE_SLOTS as u64 + 2 {
+    autosave(&dir, &save_game(&config, ticks)).unwrap();
+  }
+  // the newest first, and the oldest dropped
+  for slot in 1..=AUTOSAVE_SLOTS {
+    let save = SaveGame::read(&autosave_path(&dir, slot)).unwrap();
+    assert_eq!(save.state.ticks, (AUTOSAVE_SLOTS + 2 - slot) as u64);
+  }
+  assert!(!autosave_path(&dir, AUTOSAVE_SLOTS + 1).exists());
+}
+
+#[test]
+fn autosaves_are_due_each_month() {
+  let mut state = GameState::new();
+  let mut due = 0;
+  for _ in 0..24 * 366 {
+    let date = state.date;
+    tick(&mut state);
+    if is_autosave_due(&date, &state.date) {
+      due += 1;
+    }
+  }
+  assert_eq!(due, 12);
+}
@@ -0,0 +1,107 @@
+use mapgame::{
+  geo_drawable::GeoPolygons,
+  simplify::{DETAIL_TOLERANCES, simplify_shapes},
+};
+use std::collections::HashSet;
+
+type Key = [u64; 2];
+
+fn key(point: &[f64]) -> Key {
+  [point[0].to_bits(), point[1].to_bits()]
+}
+
+/* A wiggly border running north from (0, 0) to (0, 10), with wiggles of many sizes */
+fn border() -> Vec<Vec<f64>> {
+  (0..=200)
+    .map(|i| {
+      let y = i as f64 / 20.0;
+      let x = if i == 0 || i == 200 {
+        0.0
+      } else {
+        0.3 * (i as f64 * 1.7).sin() + 0.05 * (i as f64 * 7.3).cos()
+      };
+      vec![x, y]
+    })
+    .collect()
+}
+
+/* West and east of the border, which both follow point for point */
+fn provinces() -> (GeoPolygons, GeoPolygons) {
+  let border = border();
+  let mut west = vec![vec![-5.0, 10.0], vec![-5.0, 0.0]];
+  west.extend(border.iter().cloned());
+  west.push(west[0].clone());
+  let mut east = vec![vec![5.0, 0.0], vec![5.0, 10.0]];
+  east.extend(border.iter().rev().cloned());
+  east.push(east[0].clone());
+  (vec![vec![west]], vec![vec![east]])
+}
+
+/* A round island, sharing no points with anything */
+fn island() -> GeoPolygons {
+  let mut ring: Vec<Vec<f64>> = (0..100)
+    .map(|i| {
+      let angle = i as f64 / 100.0 * std::f64::consts::TAU;
+      vec![20.0 + angle.cos(), 5.0 + angle.sin()]
+    })
+    .collect();
+  ring.push(ring[0].clone());
+  vec![vec![ring]]
+}
+
+fn tolerances() -> Vec<f64> {
+  DETAIL_TOLERANCES
+    .iter()
+    .copied()
+    .chain([0.5, 1.0, 5.0, 100.0])
+    .collect()
+}
+
+/* The points of a shape which lie on the border */
+fn kept_border_points(shape: &GeoPolygons, border: &HashSet<Key>) -> HashSet<Key> {
+  shape
+    .iter()
+    .flatten()
+    .flatten()
+    .map(|point| key(point))
+    .filter(|key| border.contains(key))
+    .collect()
+}
+
+#[test]
+fn shared_borders_simplify_the_same_from_both_sides() {
+  let (west, east) = provinces();
+  let border: HashSet<Key> = border().iter().map(|point| key(point)).collect();
+  let tolerances = tolerances();
+  let levels = simplify_shapes(&[&west, &east], &tolerances);
+  for (tolerance, level) in tolerances.iter().zip(&levels) {
+    let (west, east) = (&level[0], &level[1]);
+    let kept = kept_border_points(west, &border);
+    assert_eq!(kept, kept_border_points(east, &border), "at {}", tolerance);
+    // the ends are junctions, so always kept
+    assert!(kept.contains(&key(&[0.0, 0.0])) && kept.contains(&key(&[0.0, 10.0])));
+  }
+  // coarser levels keep fewer points
+  let counts: Vec<usize> = levels
+    .iter()
+    .map(|level| kept_border_points(&level[0], &border).len())
+    .collect();
+  assert!(counts.windows(2).all(|pair| pair[0] >= pair[1]));
+  assert!(counts[0] < border.len());
+}
+
+#[test]
+fn simplified_rings_keep_at_least_three_points() {
+  let (west, east) = provinces();
+  let island = island();
+  let tolerances = tolerances();
+  let levels = simplify_shapes(&[&west, &east, &island], &tolerances);
+  for (tolerance, level) in tolerances.iter().zip(&levels) {
+    for ring in level.iter().flatten().flatten() {
+      assert!(ring.len() >= 4, "at {}: {:?}", tolerance, ring);
+      assert_eq!(ring.first(), ring.last());
+    }
+  }
+  // the island keeps its shape while it's larger than the tolerance
+  assert!(!levels[0][2].is_empty());
+}
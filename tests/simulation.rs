@@ -0,0 +1,97 @@
+use mapgame::{
+  calendar::GameDate,
+  simulation::{GameState, MAX_CATCH_UP, SPEEDS, SimulationClock, tick},
+};
+
+// a frame at 60fps
+const FRAME: f64 = 1.0 / 60.0;
+
+fn running_clock(speed: usize) -> SimulationClock {
+  let mut clock = SimulationClock::new();
+  clock.set_speed(speed);
+  clock.set_paused(false);
+  clock
+}
+
+#[test]
+fn ticks_advance_the_date_by_an_hour() {
+  let mut state = GameState::starting_at(GameDate::new(2000, 1, 1, 0));
+  for _ in 0..25 {
+    tick(&mut state);
+  }
+  assert_eq!(state.ticks, 25);
+  assert_eq!(state.date, GameDate::new(2000, 1, 2, 1));
+}
+
+#[test]
+fn dates_roll_over_months_years_and_leap_days() {
+  let mut date = GameDate::new(1999, 12, 31, 23);
+  date.advance_hours(1);
+  assert_eq!(date, GameDate::new(2000, 1, 1, 0));
+
+  let mut date = GameDate::new(2000, 2, 28, 23);
+  date.advance_hours(1);
+  assert_eq!(date, GameDate::new(2000, 2, 29, 0));
+
+  let mut date = GameDate::new(1900, 2, 28, 23);
+  date.advance_hours(1);
+  assert_eq!(date, GameDate::new(1900, 3, 1, 0));
+
+  assert_eq!(
+    GameDate::new(2000, 3, 5, 7).to_string(),
+    "5 March 2000, 07:00"
+  );
+}
+
+#[test]
+fn paused_clock_runs_no_ticks() {
+  let mut clock = SimulationClock::new();
+  let mut state = GameState::new();
+  assert!(clock.is_paused());
+  for _ in 0..60 {
+    clock.advance(&mut state, FRAME);
+  }
+  assert_eq!(state, GameState::new());
+}
+
+#[test]
+fn tick_rate_follows_the_speed_not_the_frame_rate() {
+  for speed in 1..=SPEEDS.len() {
+    for frame in [FRAME, 1.0 / 144.0, 1.0 / 30.0] {
+      let mut clock = running_clock(speed);
+      let mut state = GameState::new();
+      let frames = (10.0 / frame).round() as u32;
+      for _ in 0..frames {
+        clock.advance(&mut state, frame);
+      }
+      let expected = SPEEDS[speed - 1] * 10.0;
+      assert!(
+        (state.ticks as f64 - expected).abs() <= 1.0,
+        "speed {} at {:.0}fps ran {} ticks in 10s, expected {}",
+        speed,
+        1.0 / frame,
+        state.ticks,
+        expected
+      );
+    }
+  }
+}
+
+#[test]
+fn stalls_only_catch_up_a_little() {
+  let mut clock = running_clock(5);
+  let mut state = GameState::new();
+  let ticks = clock.advance(&mut state, 10.0);
+  assert_eq!(ticks as f64, (MAX_CATCH_UP * SPEEDS[4]).ceil());
+}
+
+#[test]
+fn speed_is_clamped() {
+  let mut clock = SimulationClock::new();
+  clock.slower();
+  assert_eq!(clock.speed(), 1);
+  clock.set_speed(9);
+  assert_eq!(clock.speed(), SPEEDS.len());
+  clock.faster();
+  assert_eq!(clock.speed(), SPEEDS.len());
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::{RUS_PATH, chukotka, load_world_map};
+use mapgame::{
+  errors::FeatureRef, geo_drawable::GeoDrawable, math::Vector2f, projection::Equirectangular,
+  triangulate::triangulate, world_map::LOAD_BOUNDS,
+};
+use std::{f32::consts::TAU, path::Path};
+
+fn ring(points: &[(f32, f32)]) -> Vec<Vector2f> {
+  points.iter().map(|(x, y)| Vector2f::new(*x, *y)).collect()
+}
+
+/* A regular polygon, or a star if `inner` is less than `radius` */
+fn star(points: usize, radius: f32, inner: f32) -> Vec<Vector2f> {
+  (0..points * 2)
+    .map(|i| {
+      let angle = i as f32 / (points * 2) as f32 * TAU;
+      let distance = if i % 2 == 0 { radius } else { inner };
+      Vector2f::new(distance * angle.cos(), distance * angle.sin())
+    })
+    .collect()
+}
+
+/* Shoelace formula in f64, as f32 loses too much to compare small islands' areas */
+fn area(ring: &[Vector2f]) -> f64 {
+  let mut area = 0.0;
+  for (i, a) in ring.iter().enumerate() {
+    let b = ring[(i + 1) % ring.len()];
+    area += a.x as f64 * b.y as f64 - b.x as f64 * a.y as f64;
+  }
+  (area / 2.0).abs()
+}
+
+fn rings_area(rings: &[Vec<Vector2f>]) -> f64 {
+  match rings.split_first() {
+    Some((outer, holes)) => area(outer) - holes.iter().map(|hole| area(hole)).sum::<f64>(),
+    None => 0.0,
+  }
+}
+
+/* The total area of the triangles, which overlapping or missing triangles would throw off */
+fn triangles_area(triangles: &[usize], points: &[Vector2f]) -> f64 {
+  triangles
+    .chunks(3)
+    .map(|triangle| {
+      area(&[
+        points[triangle[0]],
+        points[triangle[1]],
+        points[triangle[2]],
+      ])
+    })
+    .sum()
+}
+
+/* Triangulates the rings, checking the triangles cover exactly the polygon, and returns how many
+there are */
+fn check(rings: &[Vec<Vector2f>]) -> usize {
+  let slices: Vec<&[Vector2f]> = rings.iter().map(|ring| ring.as_slice()).collect();
+  let triangles = triangulate(&slices);
+  let points: Vec<Vector2f> = rings.iter().flatten().copied().collect();
+  assert_eq!(triangles.len() % 3, 0);
+  assert!(triangles.iter().all(|i| *i < points.len()));
+  let (area, expected) = (triangles_area(&triangles, &points), rings_area(rings));
+  assert!(
+    (area - expected).abs() <= expected * 1e-4,
+    "triangles cover {} of {}",
+    area,
+    expected
+  );
+  triangles.len() / 3
+}
+
+#[test]
+fn convex_polygons() {
+  let square = ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+  assert_eq!(check(std::slice::from_ref(&square)), 2);
+  // either winding order
+  let clockwise: Vec<Vector2f> = square.into_iter().rev().collect();
+  assert_eq!(check(&[clockwise]), 2);
+  assert_eq!(check(&[star(6, 10.0, 10.0)]), 10);
+  assert_eq!(check(&[star(100, 10.0, 10.0)]), 198);
+}
+
+#[test]
+fn concave_polygons() {
+  let l_shape = ring(&[
+    (0.0, 0.0),
+    (10.0, 0.0),
+    (10.0, 4.0),
+    (4.0, 4.0),
+    (4.0, 10.0),
+    (0.0, 10.0),
+  ]);
+  assert_eq!(check(&[l_shape]), 4);
+  assert_eq!(check(&[star(5, 10.0, 4.0)]), 8);
+  // enough points to be ear-checked by z-order
+  assert_eq!(check(&[star(60, 10.0, 7.0)]), 118);
+}
+
+#[test]
+fn polygons_with_holes() {
+  let outer = ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+  let hole = ring(&[(2.0, 2.0), (4.0, 2.0), (4.0, 4.0), (2.0, 4.0)]);
+  let other_hole = ring(&[(6.0, 6.0), (8.0, 6.0), (7.0, 8.0)]);
+  // each hole adds its points and two more triangles
+  assert_eq!(check(&[outer.clone(), hole.clone()]), 8);
+  assert_eq!(check(&[outer, hole, other_hole]), 13);
+
+  let star_with_hole = vec![star(40, 10.0, 8.0), star(20, 3.0, 2.0)];
+  assert_eq!(check(&star_with_hole), 80 + 40 + 2 - 2);
+}
+
+#[test]
+fn collinear_and_duplicate_points() {
+  let collinear = ring(&[
+    (0.0, 0.0),
+    (5.0, 0.0),
+    (10.0, 0.0),
+    (10.0, 5.0),
+    (10.0, 10.0),
+    (5.0, 10.0),
+    (0.0, 10.0),
+    (0.0, 5.0),
+  ]);
+  assert!(check(&[collinear]) <= 6);
+  let duplicates = ring(&[
+    (0.0, 0.0),
+    (0.0, 0.0),
+    (10.0, 0.0),
+    (10.0, 10.0),
+    (10.0, 10.0),
+    (10.0, 10.0),
+    (0.0, 10.0),
+  ]);
+  assert!(check(&[duplicates]) <= 5);
+  // nothing to fill
+  assert_eq!(check(&[ring(&[(0.0, 0.0), (5.0, 5.0), (10.0, 10.0)])]), 0);
+  assert_eq!(check(&[ring(&[(1.0, 1.0), (1.0, 1.0), (1.0, 1.0)])]), 0);
+  assert!(triangulate(&[]).is_empty());
+}
+
+/* As `check`, over every polygon of the shape */
+fn check_shape(geo_drawable: &GeoDrawable) {
+  let points: Vec<Vector2f> = geo_drawable
+    .vector_polygons
+    .iter()
+    .flatten()
+    .flatten()
+    .copied()
+    .collect();
+  let area = triangles_area(&geo_drawable.triangles, &points);
+  let expected: f64 = geo_drawable
+    .vector_polygons
+    .iter()
+    .map(|polygon| rings_area(polygon))
+    .sum();
+  assert!(
+    (area - expected).abs() <= expected * 1e-3,
+    "{}'s triangles cover {} of {}",
+    geo_drawable.id,
+    area,
+    expected
+  );
+}
+
+#[test]
+fn chukotka_is_filled() {
+  let geo_drawable = GeoDrawable::new(
+    chukotka(),
+    FeatureRef::new(Path::new(RUS_PATH), 0),
+    &LOAD_BOUNDS,
+    &Equirectangular,
+    "shapeName",
+    Some("shapeID"),
+  )
+  .unwrap();
+  check_shape(&geo_drawable);
+  for polygon in &geo_drawable.vector_polygons {
+    check(polygon);
+  }
+}
+
+#[test]
+fn earth_is_filled() {
+  let world_map = load_world_map();
+  for nation in world_map.nations().values() {
+    check_shape(&nation.geo_drawable);
+  }
+}
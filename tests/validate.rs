@@ -0,0 +1,163 @@
+mod common;
+
+use common::{TempDir, feature_collection, square};
+use mapgame::{
+  config::MapConfig,
+  validate::{Problem, Severity, validate_map},
+};
+use serde_json::{Value, json};
+use std::fs;
+
+fn feature(properties: Value, geometry: Value) -> Value {
+  json!({ "type": "Feature", "properties": properties, "geometry": geometry })
+}
+
+/* Writes a map of the given nations, with provinces files by nation ID, and validates it */
+fn validate(name: &str, nations: Vec<Value>, provinces: Vec<(&str, Vec<Value>)>) -> Vec<Problem> {
+  let dir = TempDir::new(name);
+  fs::write(dir.join("nations.geojson"), feature_collection(nations)).unwrap();
+  fs::write(dir.join("provinces.json"), "{}").unwrap();
+  fs::create_dir_all(dir.join("provinces")).unwrap();
+  for (nation_id, features) in provinces {
+    let path = dir.join("provinces").join(format!("{}.geojson", nation_id));
+    fs::write(path, feature_collection(features)).unwrap();
+  }
+  let config = MapConfig::load(name, &dir).unwrap();
+  validate_map(&config)
+}
+
+/* Whether there's an error for the feature whose message contains `text` */
+fn has_error(problems: &[Problem], feature: &str, text: &str) -> bool {
+  problems.iter().any(|problem| {
+    problem.severity == Severity::Error
+      && problem.feature.as_deref() == Some(feature)
+      && problem.message.contains(text)
+  })
+}
+
+fn errors(problems: &[Problem]) -> usize {
+  problems
+    .iter()
+    .filter(|problem| problem.severity == Severity::Error)
+    .count()
+}
+
+fn aland() -> Value {
+  square(0.0, 0.0, 40.0, json!({ "id": "ALA", "name": "Aland" }))
+}
+
+#[test]
+fn valid_maps_have_no_errors() {
+  let provinces = vec![
+    square(0.0, 0.0, 20.0, json!({ "id": "A-1", "name": "West" })),
+    square(20.0, 0.0, 20.0, json!({ "id": "A-2", "name": "East" })),
+  ];
+  let problems = validate("validate-valid", vec![aland()], vec![("ALA", provinces)]);
+  assert_eq!(errors(&problems), 0, "{:?}", problems);
+}
+
+#[test]
+fn missing_and_duplicate_ids_are_errors() {
+  let nations = vec![
+    aland(),
+    square(
+      40.0,
+      0.0,
+      40.0,
+      json!({ "id": "ALA", "name": "Aland again" }),
+    ),
+    square(80.0, 0.0, 40.0, json!({ "name": "Nameless" })),
+    square(120.0, 0.0, 40.0, json!({ "id": "BOR" })),
+  ];
+  let problems = validate("validate-ids", nations, Vec::new());
+  assert!(has_error(&problems, "ALA", "same ID as an earlier feature"));
+  assert!(has_error(&problems, "#2", "missing its ID"));
+  assert!(has_error(&problems, "BOR", "missing its name"));
+  assert_eq!(errors(&problems), 3, "{:?}", problems);
+}
+
+#[test]
+fn bad_geometry_is_an_error() {
+  let nations = vec![
+    feature(
+      json!({ "id": "PNT", "name": "Point" }),
+      json!({ "type": "Point", "coordinates": [0.0, 0.0] }),
+    ),
+    feature(
+      json!({ "id": "OPN", "name": "Open" }),
+      json!({
+        "type": "Polygon",
+        "coordinates": [[[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [1.0, 1.0]]]
+      }),
+    ),
+    feature(
+      json!({ "id": "BOW", "name": "Bowtie" }),
+      json!({
+        "type": "Polygon",
+        "coordinates": [[[20.0, 0.0], [30.0, 10.0], [30.0, 0.0], [20.0, 10.0], [20.0, 0.0]]]
+      }),
+    ),
+    feature(
+      json!({ "id": "SHT", "name": "Short" }),
+      json!({ "type": "Polygon", "coordinates": [[[40.0, 0.0], [50.0, 0.0], [40.0, 0.0]]] }),
+    ),
+    feature(json!({ "id": "NUL", "name": "Null" }), Value::Null),
+  ];
+  let problems = validate("validate-geometry", nations, Vec::new());
+  assert!(has_error(
+    &problems,
+    "PNT",
+    "unsupported geometry type Point"
+  ));
+  assert!(has_error(&problems, "OPN", "unclosed ring"));
+  assert!(has_error(&problems, "BOW", "self-intersecting ring"));
+  assert!(has_error(&problems, "SHT", "only 3 positions"));
+  assert!(has_error(&problems, "NUL", "no geometry"));
+  assert_eq!(errors(&problems), 5, "{:?}", problems);
+}
+
+#[test]
+fn provinces_outside_their_nation_are_errors() {
+  let provinces = vec![
+    square(0.0, 0.0, 20.0, json!({ "id": "A-1", "name": "West" })),
+    // mostly in the sea next to Aland
+    square(35.0, 0.0, 20.0, json!({ "id": "A-2", "name": "Shore" })),
+    square(60.0, 60.0, 10.0, json!({ "id": "A-3", "name": "Faraway" })),
+  ];
+  let problems = validate("validate-outside", vec![aland()], vec![("ALA", provinces)]);
+  assert!(!has_error(&problems, "A-1", "outside"));
+  assert!(has_error(
+    &problems,
+    "A-2",
+    "lies mostly outside of nation ALA"
+  ));
+  assert!(has_error(
+    &problems,
+    "A-3",
+    "lies mostly outside of nation ALA"
+  ));
+}
+
+#[test]
+fn unused_files_and_missing_provinces_are_warnings() {
+  let nations = vec![
+    aland(),
+    square(40.0, 0.0, 40.0, json!({ "id": "BOR", "name": "Borduria" })),
+  ];
+  let provinces = vec![
+    ("ALA", vec![aland()]),
+    (
+      "XXX",
+      vec![square(0.0, 0.0, 1.0, json!({ "id": "X-1", "name": "X" }))],
+    ),
+  ];
+  let problems = validate("validate-warnings", nations, provinces);
+  assert_eq!(errors(&problems), 0, "{:?}", problems);
+  let warned = |text: &str| {
+    problems
+      .iter()
+      .any(|problem| problem.message.contains(text))
+  };
+  assert!(warned("has no provinces file"));
+  assert!(warned("is not used"));
+}